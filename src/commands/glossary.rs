@@ -0,0 +1,477 @@
+//! 术语表管理命令
+//!
+//! 实现 `pmt glossary list|search|add|remove|show|explain|import|export|lint`，通过 Glossary 类型读写
+//! glossary_custom 中的自定义术语表，默认术语表只读；
+//! `pmt glossary harvest` 从游戏原版本地化中采集术语，同样写入自定义术语表。
+
+use crate::config::{GlossaryConflictPolicy, TranslationTask};
+use crate::error::{Result, TranslateError, TranslationError};
+use crate::translate::{
+    Glossary, GlossaryFormat, GlossaryItem, GlossaryKind, GlossaryOrigin, HarvestOptions,
+    export_glossary, find_glossary_files, harvest_glossary, import_glossary, lint_glossary_file,
+    load_glossary_layers, load_task_glossary_layers, merge_glossaries_with_policy,
+    read_language_tree, task_glossary_refs,
+};
+use crate::utils::data_file_for_write;
+use std::path::Path;
+
+/// 合并术语表时用于检查重复源术语的源语言
+const CONFLICT_SOURCE_LANG: &str = "english";
+
+/// 列出术语表中的所有条目，并标注每个条目来自默认还是自定义术语表
+pub fn glossary_list(name: &str) -> Result<()> {
+    let merged = load_merged(name)?;
+    let mut keys: Vec<&String> = merged.entries().keys().collect();
+    keys.sort();
+    for key in keys {
+        print_entry(&merged, key);
+    }
+    log::info!("Glossary '{}' has {} entries", name, merged.len());
+    Ok(())
+}
+
+/// 搜索 key 或术语中包含 query 的条目
+pub fn glossary_search(name: &str, query: &str, lang: Option<&str>) -> Result<()> {
+    let merged = load_merged(name)?;
+    let found = merged.search(query, lang);
+    for (key, _) in &found {
+        print_entry(&merged, key);
+    }
+    log::info!("Found {} entries matching '{}'", found.len(), query);
+    Ok(())
+}
+
+/// 显示单个条目的所有语言，以及定义了该条目的每个术语表文件
+pub fn glossary_show(name: &str, key: &str) -> Result<()> {
+    let layers = load_glossary_layers(name)?;
+    if layers.is_empty() {
+        return Err(not_found(name));
+    }
+    if !print_layer_stack(&layers, key) {
+        return Err(glossary_error(format!(
+            "Key '{}' not found in glossary '{}'",
+            key, name
+        )));
+    }
+    Ok(())
+}
+
+/// 按任务配置的术语表层级解释条目的来源：依次列出生效和被覆盖的定义，以及各自的层级与优先级
+pub fn glossary_explain(task_file: &Path, key: &str) -> Result<()> {
+    let (_, tasks) = TranslationTask::from_file(task_file)?;
+    let mut found = false;
+    for (i, task) in tasks.iter().enumerate() {
+        let refs = task_glossary_refs(task);
+        let stack: Vec<String> = refs.iter().map(|r| r.to_string()).collect();
+        println!("Task {}: {}", i + 1, stack.join(" < "));
+        let layers = load_task_glossary_layers(task)?;
+        if !print_layer_stack(&layers, key) {
+            println!("  '{}' is not defined in any layer", key);
+        } else {
+            found = true;
+        }
+    }
+    if !found {
+        return Err(glossary_error(format!(
+            "Key '{}' not found in any glossary of {}",
+            key,
+            task_file.display()
+        )));
+    }
+    Ok(())
+}
+
+/// 从优先级最高的层开始打印定义了 key 的每一层，返回是否找到
+fn print_layer_stack(layers: &[(GlossaryOrigin, Glossary)], key: &str) -> bool {
+    let mut found = false;
+    for (origin, glossary) in layers.iter().rev() {
+        if let Some(item) = glossary.get(key) {
+            let state = if found { "overridden" } else { "effective" };
+            println!("{} [{}] from {}", key, state, origin);
+            print_item(item);
+            found = true;
+        }
+    }
+    found
+}
+
+fn print_item(item: &GlossaryItem) {
+    for (lang, term) in item.all_terms() {
+        println!("  {}: {}", lang, term);
+        if let Some(note) = item.lang_note(lang) {
+            println!("    note: {}", note);
+        }
+        if !item.alternatives(lang).is_empty() {
+            println!("    alternatives: {}", item.alternatives(lang).join(", "));
+        }
+        if !item.forbidden(lang).is_empty() {
+            println!("    forbidden: {}", item.forbidden(lang).join(", "));
+        }
+    }
+    if let Some(pos) = &item.pos {
+        println!("  pos: {}", pos);
+    }
+    if item.case_sensitive {
+        println!("  case sensitive: true");
+    }
+    if let Some(note) = &item.note {
+        println!("  note: {}", note);
+    }
+}
+
+/// 向自定义术语表添加或更新条目
+///
+/// terms 为 `语言=术语` 形式的列表；未指定 key 时使用英文术语的小写形式。
+/// 已存在的条目只更新给出的语言。
+pub fn glossary_add(name: &str, key: Option<&str>, terms: &[String]) -> Result<()> {
+    let mut pairs = Vec::new();
+    for term in terms {
+        let (lang, value) = term.split_once('=').ok_or_else(|| {
+            glossary_error(format!("Invalid term '{}', expected LANG=TERM", term))
+        })?;
+        pairs.push((lang.trim(), value.trim().to_string()));
+    }
+    let key = match key {
+        Some(key) => key.to_string(),
+        None => pairs
+            .iter()
+            .find(|(lang, _)| *lang == "english")
+            .map(|(_, term)| term.to_lowercase())
+            .ok_or_else(|| {
+                glossary_error("Either --key or an english term is required".to_string())
+            })?,
+    };
+
+    let (path, mut custom) = load_custom_for_write(name)?;
+    // 自定义术语表中没有该条目时，以默认术语表中的条目为基础，避免覆盖后丢失其他语言
+    let mut item = match custom.get(&key) {
+        Some(item) => item.clone(),
+        None => load_glossary_layers(name)?
+            .into_iter()
+            .find_map(|(_, glossary)| glossary.get(&key).cloned())
+            .unwrap_or_default(),
+    };
+    for (lang, term) in pairs {
+        item.set(lang, Some(term))?;
+    }
+    let replaced = custom.insert(key.clone(), item)?;
+    custom.save_json_file(&path)?;
+
+    let action = if replaced.is_some() {
+        "Updated"
+    } else {
+        "Added"
+    };
+    log::info!("{} '{}' in {}", action, key, path.display());
+    Ok(())
+}
+
+/// 从自定义术语表中删除条目
+///
+/// 默认术语表随程序发布，不可修改；若条目只存在于默认术语表中则返回错误。
+pub fn glossary_remove(name: &str, key: &str) -> Result<()> {
+    let (path, mut custom) = load_custom_for_write(name)?;
+    if custom.remove(key).is_none() {
+        let in_default = load_glossary_layers(name)?
+            .iter()
+            .any(|(origin, glossary)| {
+                origin.kind == GlossaryKind::Default && glossary.get(key).is_some()
+            });
+        let reason = if in_default {
+            "it is defined in the default glossary, which is read-only"
+        } else {
+            "it does not exist"
+        };
+        return Err(glossary_error(format!(
+            "Cannot remove '{}' from custom glossary '{}': {}",
+            key, name, reason
+        )));
+    }
+    custom.save_json_file(&path)?;
+    log::info!("Removed '{}' from {}", key, path.display());
+    Ok(())
+}
+
+/// 从 CSV/TSV/TBX 文件导入条目到自定义术语表
+///
+/// 已存在的条目只更新文件中给出的语言；replace 为 true 时先清空自定义术语表。
+/// 无法导入的行会逐条报告，但不影响其他行的导入。
+pub fn glossary_import(
+    name: &str,
+    file: &Path,
+    format: Option<GlossaryFormat>,
+    replace: bool,
+) -> Result<()> {
+    let format = resolve_format(file, format)?;
+    let content = std::fs::read_to_string(file)?;
+    let (imported, report) = import_glossary(&content, format)?;
+    for code in &report.ignored_languages {
+        log::warn!("Ignored unknown language column '{}'", code);
+    }
+    for row in &report.rejected {
+        log::warn!("Rejected {}", row);
+    }
+
+    let (path, mut custom) = load_custom_for_write(name)?;
+    // 交换格式只包含术语与备注，不能覆盖带有扩展信息的条目，以免丢失这些信息
+    let extended: Vec<&String> = custom
+        .entries()
+        .iter()
+        .filter(|(_, item)| item.has_extended_fields())
+        .map(|(key, _)| key)
+        .collect();
+    if replace && !extended.is_empty() {
+        return Err(glossary_error(format!(
+            "Refusing to replace {}: entries {} have notes, alternatives, forbidden terms or part of speech that the imported file cannot carry",
+            path.display(),
+            extended
+                .iter()
+                .map(|key| key.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+    if replace {
+        custom = Glossary::default();
+    }
+    let mut kept = 0;
+    for (key, item) in imported.entries() {
+        if let Some(existing) = custom.get(key)
+            && existing.has_extended_fields()
+            && item
+                .all_terms()
+                .iter()
+                .any(|(lang, term)| existing.get(lang) != Some(*term))
+        {
+            log::warn!(
+                "Kept existing entry '{}': it has extended fields and its terms differ from the imported ones",
+                key
+            );
+            kept += 1;
+            continue;
+        }
+        let mut merged = custom.get(key).cloned().unwrap_or_default();
+        for (lang, term) in item.all_terms() {
+            merged.set(lang, Some(term.to_string()))?;
+        }
+        if item.note.is_some() {
+            merged.note = item.note.clone();
+        }
+        custom.insert(key.clone(), merged)?;
+    }
+    custom.save_json_file(&path)?;
+    log::info!(
+        "Imported {} entries into {} ({} rejected, {} kept)",
+        report.imported - kept,
+        path.display(),
+        report.rejected.len(),
+        kept
+    );
+    Ok(())
+}
+
+/// 将合并后的术语表（默认 + 自定义）导出为 CSV/TSV/TBX 文件
+pub fn glossary_export(
+    name: &str,
+    file: &Path,
+    format: Option<GlossaryFormat>,
+    langs: &[String],
+) -> Result<()> {
+    let format = resolve_format(file, format)?;
+    let merged = load_merged(name)?;
+    let extended = merged
+        .entries()
+        .values()
+        .filter(|item| item.has_extended_fields())
+        .count();
+    if extended > 0 {
+        log::warn!(
+            "{} entries have notes, alternatives, forbidden terms or part of speech that are not exported",
+            extended
+        );
+    }
+    let langs: Vec<&str> = langs.iter().map(|s| s.as_str()).collect();
+    let content = export_glossary(&merged, format, &langs)?;
+    std::fs::write(file, content)?;
+    log::info!("Exported {} entries to {}", merged.len(), file.display());
+    Ok(())
+}
+
+/// 对齐游戏原版的源语言与目标语言本地化，采集术语并写入术语表
+///
+/// 默认写入数据目录中的自定义术语表 glossary_custom/NAME.json，默认术语表只读，不会被覆盖；
+/// 指定 `output` 时写入该文件。
+/// 术语表文件已存在时保留其中的条目，只添加新术语；overwrite 为 true 时采集结果覆盖同名条目
+pub fn glossary_harvest(
+    game_dir: &Path,
+    name: &str,
+    output: Option<&Path>,
+    options: &HarvestOptions,
+    overwrite: bool,
+) -> Result<()> {
+    let source_dir = game_language_dir(game_dir, &options.source_lang)?;
+    let target_dir = game_language_dir(game_dir, &options.target_lang)?;
+    log::info!("Reading source localisation from {}", source_dir.display());
+    let source = read_language_tree(&source_dir)?;
+    log::info!("Reading target localisation from {}", target_dir.display());
+    let target = read_language_tree(&target_dir)?;
+
+    let result = harvest_glossary(&source, &target, options)?;
+    log::info!(
+        "Aligned {} keys, {} name-like candidates, {} below frequency {}, {} with multiple translations",
+        result.aligned,
+        result.candidates,
+        result.below_frequency,
+        options.min_frequency,
+        result.ambiguous
+    );
+
+    let (path, mut glossary) = match output {
+        Some(path) if path.exists() => (path.to_path_buf(), Glossary::from_json_file_strict(path)?),
+        Some(path) => (path.to_path_buf(), Glossary::default()),
+        None => load_custom_for_write(name)?,
+    };
+    let mut added = 0;
+    for (key, item) in result.glossary.entries() {
+        if overwrite || glossary.get(key).is_none() {
+            glossary.insert(key.clone(), item.clone())?;
+            added += 1;
+        }
+    }
+    glossary.save_json_file(&path)?;
+    log::info!(
+        "Wrote {} harvested terms to {} ({} entries in total)",
+        added,
+        path.display(),
+        glossary.len()
+    );
+    Ok(())
+}
+
+/// 检查术语表文件中不合法的条目，以及按顺序合并这些术语表时产生的冲突
+///
+/// 发现任何问题时返回错误，便于在脚本中使用
+pub fn glossary_lint(names: &[String], source_lang: &str) -> Result<()> {
+    let mut issues = 0;
+    let mut layers = Vec::new();
+    for name in names {
+        let found = load_glossary_layers(name)?;
+        if found.is_empty() {
+            return Err(not_found(name));
+        }
+        for (origin, glossary) in found {
+            for issue in lint_glossary_file(&origin.path)? {
+                println!("{}: {}", origin.path.display(), issue);
+                issues += 1;
+            }
+            layers.push(glossary);
+        }
+    }
+    let (_, conflicts) =
+        merge_glossaries_with_policy(&layers, source_lang, GlossaryConflictPolicy::CustomWins)?;
+    for conflict in &conflicts {
+        println!("conflict: {}", conflict);
+    }
+    issues += conflicts.len();
+
+    if issues > 0 {
+        return Err(glossary_error(format!(
+            "Found {} issues in glossaries {}",
+            issues,
+            names.join(", ")
+        )));
+    }
+    log::info!("No issues found in glossaries {}", names.join(", "));
+    Ok(())
+}
+
+/// 游戏目录下某语言的本地化目录，兼容 localisation 与 localization 两种拼写
+fn game_language_dir(game_dir: &Path, lang: &str) -> Result<std::path::PathBuf> {
+    ["localisation", "localization"]
+        .iter()
+        .map(|folder| game_dir.join(folder).join(lang))
+        .find(|dir| dir.is_dir())
+        .ok_or_else(|| {
+            TranslationError::FileNotFound(format!(
+                "No localisation/{} directory in {}",
+                lang,
+                game_dir.display()
+            ))
+        })
+}
+
+fn resolve_format(file: &Path, format: Option<GlossaryFormat>) -> Result<GlossaryFormat> {
+    format
+        .or_else(|| GlossaryFormat::from_path(file))
+        .ok_or_else(|| {
+            glossary_error(format!(
+                "Cannot infer glossary format from '{}', use --format csv|tsv|tbx",
+                file.display()
+            ))
+        })
+}
+
+/// 加载默认与自定义术语表并合并，自定义覆盖默认，与翻译时一样检测冲突
+fn load_merged(name: &str) -> Result<Glossary> {
+    let layers: Vec<Glossary> = load_glossary_layers(name)?
+        .into_iter()
+        .map(|(_, glossary)| glossary)
+        .collect();
+    if layers.is_empty() {
+        return Err(not_found(name));
+    }
+    let (merged, conflicts) = merge_glossaries_with_policy(
+        &layers,
+        CONFLICT_SOURCE_LANG,
+        GlossaryConflictPolicy::CustomWins,
+    )?;
+    for conflict in &conflicts {
+        log::warn!("Glossary conflict: {}", conflict);
+    }
+    Ok(merged)
+}
+
+/// 加载用于写入的自定义术语表，文件不存在时返回空术语表
+fn load_custom_for_write(name: &str) -> Result<(std::path::PathBuf, Glossary)> {
+    let (_, custom) = find_glossary_files(name)?;
+    match custom {
+        Some(path) => {
+            let glossary = Glossary::from_json_file_strict(&path)?;
+            Ok((path, glossary))
+        }
+        None => {
+            let path = data_file_for_write(&format!("glossary_custom/{}.json", name))?;
+            Ok((path, Glossary::default()))
+        }
+    }
+}
+
+fn print_entry(glossary: &Glossary, key: &str) {
+    let Some(item) = glossary.get(key) else {
+        return;
+    };
+    let source = glossary
+        .origin(key)
+        .map(|origin| origin.kind.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("[{}] {}: {}", source, key, format_terms(item));
+}
+
+fn format_terms(item: &GlossaryItem) -> String {
+    item.all_terms()
+        .iter()
+        .map(|(lang, term)| format!("{}={}", lang, term))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn not_found(name: &str) -> TranslationError {
+    TranslationError::FileNotFound(format!(
+        "Glossary '{}' not found in glossary/ or glossary_custom/",
+        name
+    ))
+}
+
+fn glossary_error(message: String) -> TranslationError {
+    TranslationError::Translate(TranslateError::GlossaryError(message))
+}
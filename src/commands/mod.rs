@@ -0,0 +1,9 @@
+//! 命令模块
+//!
+//! 实现翻译、验证之外的辅助子命令，例如术语表管理与备份恢复。
+
+mod glossary;
+mod restore;
+
+pub use glossary::*;
+pub use restore::*;
@@ -0,0 +1,91 @@
+//! 备份恢复命令
+//!
+//! 实现 `pmt restore`：列出任务备份目录中的运行，或将译文文件恢复为某次运行之前的内容。
+
+use crate::config::TranslationTask;
+use crate::error::{Result, TranslationError};
+use crate::postprocess::{
+    BackupRun, list_backups, restore_backup, restore_written_hashes, run_order,
+};
+use std::path::{Path, PathBuf};
+
+/// 列出任务的所有备份
+pub fn restore_list(task_file: &Path) -> Result<()> {
+    for (dir, _) in backup_dirs(task_file)? {
+        let runs = list_backups(&dir)?;
+        log::info!("Found {} backup runs in {}", runs.len(), dir.display());
+        for run in runs {
+            println!("{} ({} files)", run.id, run.entries.len());
+            for entry in &run.entries {
+                let state = if entry.backup.is_some() {
+                    "overwritten"
+                } else {
+                    "created"
+                };
+                println!("  {} [{}]", entry.path.display(), state);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 将文件恢复为某次运行之前的内容
+///
+/// 未指定运行时使用最近一次运行，未指定文件时回滚该运行写入的所有文件；
+/// 恢复前的内容同样会备份，可以再次恢复；状态文件中这些文件的记录也恢复为该次运行之前的状态
+pub fn restore(task_file: &Path, run: Option<&str>, file: Option<&Path>) -> Result<()> {
+    // 多个任务使用不同的备份目录时，选择包含最近一次匹配运行的目录
+    let mut latest: Option<(String, PathBuf, Vec<PathBuf>)> = None;
+    for (dir, state_files) in backup_dirs(task_file)? {
+        let found = list_backups(&dir)?
+            .into_iter()
+            .rev()
+            .find(|m| run.is_none_or(|id| m.id == id) && file.is_none_or(|f| m.entry(f).is_some()));
+        if let Some(manifest) = found
+            && latest
+                .as_ref()
+                .is_none_or(|(id, _, _)| run_order(&manifest.id) > run_order(id))
+        {
+            latest = Some((manifest.id, dir, state_files));
+        }
+    }
+    let Some((id, dir, state_files)) = latest else {
+        return Err(TranslationError::FileNotFound(format!(
+            "No backup run{}{}",
+            run.map(|id| format!(" '{}'", id)).unwrap_or_default(),
+            file.map(|f| format!(" containing {}", f.display()))
+                .unwrap_or_default()
+        )));
+    };
+
+    let mut backup = BackupRun::new(&dir);
+    let restored = restore_backup(&dir, Some(&id), file, &mut backup)?;
+    for state_file in &state_files {
+        restore_written_hashes(state_file, &dir, &id, &restored, &mut backup)?;
+    }
+    for path in &restored {
+        log::info!("Restored {}", path.display());
+    }
+    log::info!("Restored {} files from backup run {}", restored.len(), id);
+    if let Some(undo) = backup.id() {
+        log::info!("The replaced versions were backed up as run {}", undo);
+    }
+    Ok(())
+}
+
+/// 任务文件中所有任务的备份目录（去重），以及使用该备份目录的任务的状态文件
+fn backup_dirs(task_file: &Path) -> Result<Vec<(PathBuf, Vec<PathBuf>)>> {
+    let (_, tasks) = TranslationTask::from_file(task_file)?;
+    let mut dirs: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+    for task in tasks {
+        match dirs.iter_mut().find(|(dir, _)| *dir == task.backup_dir) {
+            Some((_, state_files)) => {
+                if !state_files.contains(&task.state_file) {
+                    state_files.push(task.state_file);
+                }
+            }
+            None => dirs.push((task.backup_dir, vec![task.state_file])),
+        }
+    }
+    Ok(dirs)
+}
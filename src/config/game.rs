@@ -0,0 +1,221 @@
+//! 游戏配置模块
+//!
+//! 不同的 Paradox 游戏在本地化标记、目录命名和支持的语言上有所差异。
+//! 任务中的 `game` 选择对应的游戏配置，默认为 Stellaris。
+
+use serde::{Deserialize, Serialize};
+
+use crate::translate::MarkupSyntax;
+
+/// 支持的游戏
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Game {
+    /// 群星
+    #[default]
+    Stellaris,
+    /// 十字军之王 III
+    Ck3,
+    /// 维多利亚 3
+    Vic3,
+    /// 钢铁雄心 IV
+    Hoi4,
+    /// 欧陆风云 IV
+    Eu4,
+}
+
+/// 游戏配置
+#[derive(Debug, Clone, Copy)]
+pub struct GameProfile {
+    /// 游戏名称，用于提示词
+    pub name: &'static str,
+    /// 本地化目录名，`localisation` 或 `localization`
+    pub localisation_folder: &'static str,
+    /// 游戏支持的语言
+    pub languages: &'static [&'static str],
+    /// 文本中可用的标记
+    pub markup: MarkupSyntax,
+    /// 提示词中要求的译文风格
+    pub style: &'static str,
+    /// 未配置 glossaries 时使用的术语表，没有随程序提供术语表的游戏为 None
+    pub glossary: Option<&'static str>,
+}
+
+impl Game {
+    /// 游戏对应的配置
+    pub fn profile(self) -> GameProfile {
+        match self {
+            Game::Stellaris => GameProfile {
+                name: "群星（Stellaris）",
+                localisation_folder: "localisation",
+                languages: &[
+                    "english",
+                    "braz_por",
+                    "french",
+                    "german",
+                    "polish",
+                    "russian",
+                    "spanish",
+                    "japanese",
+                    "simp_chinese",
+                    "korean",
+                ],
+                markup: MarkupSyntax::STELLARIS,
+                style: "科幻风格",
+                glossary: Some("stellaris"),
+            },
+            Game::Ck3 => GameProfile {
+                name: "十字军之王 III（Crusader Kings III）",
+                localisation_folder: "localization",
+                languages: &[
+                    "english",
+                    "french",
+                    "german",
+                    "spanish",
+                    "russian",
+                    "korean",
+                    "simp_chinese",
+                    "japanese",
+                    "braz_por",
+                    "polish",
+                ],
+                markup: MarkupSyntax::JOMINI,
+                style: "中世纪历史风格",
+                glossary: None,
+            },
+            Game::Vic3 => GameProfile {
+                name: "维多利亚 3（Victoria 3）",
+                localisation_folder: "localization",
+                languages: &[
+                    "english",
+                    "braz_por",
+                    "french",
+                    "german",
+                    "japanese",
+                    "korean",
+                    "polish",
+                    "russian",
+                    "simp_chinese",
+                    "spanish",
+                    "turkish",
+                ],
+                markup: MarkupSyntax::JOMINI,
+                style: "19 世纪历史风格",
+                glossary: None,
+            },
+            Game::Hoi4 => GameProfile {
+                name: "钢铁雄心 IV（Hearts of Iron IV）",
+                localisation_folder: "localisation",
+                languages: &[
+                    "english",
+                    "braz_por",
+                    "french",
+                    "german",
+                    "polish",
+                    "russian",
+                    "spanish",
+                    "japanese",
+                    "simp_chinese",
+                ],
+                markup: MarkupSyntax::CLAUSEWITZ,
+                style: "二战时期军事历史风格",
+                glossary: None,
+            },
+            Game::Eu4 => GameProfile {
+                name: "欧陆风云 IV（Europa Universalis IV）",
+                localisation_folder: "localisation",
+                languages: &["english", "french", "german", "spanish"],
+                markup: MarkupSyntax::CLAUSEWITZ,
+                style: "近代早期历史风格",
+                glossary: None,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Game::Stellaris => "stellaris",
+            Game::Ck3 => "ck3",
+            Game::Vic3 => "vic3",
+            Game::Hoi4 => "hoi4",
+            Game::Eu4 => "eu4",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl GameProfile {
+    /// 游戏是否支持该语言
+    pub fn supports_language(&self, lang: &str) -> bool {
+        self.languages.contains(&lang)
+    }
+
+    /// 提示词中的格式标记说明
+    pub fn markup_rules(&self) -> String {
+        let mut rules = vec![
+            "   - £...£ 图标标记（例如 £energy£）",
+            "   - $...$ 变量标记（例如 $energy$ 或 $example|arguments$）",
+            "   - §...§ 颜色标记（例如 §Y...§!）",
+            "   - [...] 指令标记（例如 [Root.GetName]）",
+        ];
+        if self.markup.concept_commands {
+            rules.push(
+                "   - ['concept' text] 概念标记（例如 ['pop_growth', Growth]），其中 'concept' 原样保留，后面的说明文本需要翻译",
+            );
+        }
+        if self.markup.format_blocks {
+            rules.push("   - #format ... #! 文本格式标记（例如 #bold ...#!），格式名原样保留");
+        }
+        if self.markup.text_icons {
+            rules.push("   - @icon! 文本图标标记（例如 @gold_icon!）");
+        }
+        rules.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TranslationTask;
+
+    fn ck3_task() -> TranslationTask {
+        toml::from_str(
+            r#"
+game = "ck3"
+source_lang = "english"
+target_langs = ["simp_chinese"]
+localisation_dir = "localization"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_game_profile_from_task() {
+        let task = ck3_task();
+        assert_eq!(task.game, Game::Ck3);
+        assert_eq!(task.profile().localisation_folder, "localization");
+    }
+
+    #[test]
+    fn test_markup_rules_follow_game() {
+        let rules = Game::Ck3.profile().markup_rules();
+        assert!(rules.contains("#format ... #!"));
+        assert!(!rules.contains("['concept' text]"));
+    }
+
+    #[test]
+    fn test_supported_languages() {
+        assert!(Game::Ck3.profile().supports_language("simp_chinese"));
+        assert!(!Game::Eu4.profile().supports_language("simp_chinese"));
+    }
+
+    #[test]
+    fn test_default_glossary_only_when_shipped() {
+        assert_eq!(Game::Stellaris.profile().glossary, Some("stellaris"));
+        assert_eq!(Game::Ck3.profile().glossary, None);
+        assert!(crate::translate::task_glossary_refs(&ck3_task()).is_empty());
+    }
+}
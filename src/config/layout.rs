@@ -0,0 +1,154 @@
+//! 本地化目录布局模块
+//!
+//! Stellaris、CK3、Vic3 按语言分目录存放本地化文件（`localisation/english/`），
+//! EU4 等较早的游戏则将所有语言放在同一个目录中，只以文件名后缀 `_l_<语言>.yml` 区分。
+//! 目录名也有 `localisation` 与 `localization` 两种拼写。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::preprocess::find_localisation_files;
+
+/// 本地化目录的布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalisationLayout {
+    /// 每种语言一个目录：`{localisation_dir}/{lang}/*_l_{lang}.yml`
+    PerLanguage,
+    /// 所有语言位于同一目录：`{localisation_dir}/*_l_{lang}.yml`
+    Flat,
+}
+
+impl LocalisationLayout {
+    /// 按源语言目录是否存在推断布局
+    pub fn detect(localisation_dir: &Path, source_lang: &str) -> Self {
+        if localisation_dir.join(source_lang).is_dir() {
+            return LocalisationLayout::PerLanguage;
+        }
+        let has_language_files = find_localisation_files(localisation_dir)
+            .is_ok_and(|files| files.iter().any(|f| is_language_file(f, source_lang)));
+        if has_language_files {
+            LocalisationLayout::Flat
+        } else {
+            LocalisationLayout::PerLanguage
+        }
+    }
+
+    /// 某语言的本地化文件所在的目录
+    pub fn language_dir(self, base: &Path, lang: &str) -> PathBuf {
+        match self {
+            LocalisationLayout::PerLanguage => base.join(lang),
+            LocalisationLayout::Flat => base.to_path_buf(),
+        }
+    }
+
+    /// 目录中属于某语言的本地化文件，按路径排序
+    ///
+    /// 按语言分目录时为目录中的所有文件，所有语言位于同一目录时只取文件名以 `_l_{lang}` 结尾的文件
+    pub fn language_files(self, dir: &Path, lang: &str) -> Result<Vec<PathBuf>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let files = find_localisation_files(dir)?;
+        Ok(match self {
+            LocalisationLayout::PerLanguage => files,
+            LocalisationLayout::Flat => files
+                .into_iter()
+                .filter(|f| is_language_file(f, lang))
+                .collect(),
+        })
+    }
+}
+
+/// 文件名是否以 `_l_{lang}` 结尾（不含扩展名）
+pub fn is_language_file(path: &Path, lang: &str) -> bool {
+    let suffix = format!("l_{}", lang);
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .is_some_and(|stem| {
+            stem.strip_suffix(&suffix)
+                .is_some_and(|rest| rest.is_empty() || rest.ends_with('_'))
+        })
+}
+
+/// 配置的本地化目录不存在时，尝试 `localisation` 与 `localization` 的另一种拼写
+pub fn resolve_spelling(localisation_dir: &Path) -> PathBuf {
+    if localisation_dir.exists() {
+        return localisation_dir.to_path_buf();
+    }
+    let alternative = match localisation_dir.file_name() {
+        Some(name) if name == "localisation" => "localization",
+        Some(name) if name == "localization" => "localisation",
+        _ => return localisation_dir.to_path_buf(),
+    };
+    let alternative = localisation_dir.with_file_name(alternative);
+    if alternative.is_dir() {
+        alternative
+    } else {
+        localisation_dir.to_path_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_tree;
+
+    const FLAT: &[(&str, &str)] = &[
+        ("a_l_english.yml", "l_english:\n"),
+        ("a_l_german.yml", "l_german:\n"),
+        ("replace/b_l_english.yaml", "l_english:\n"),
+        ("control_l_english_and_more.yml", "l_english:\n"),
+    ];
+
+    #[test]
+    fn test_resolve_american_spelling() {
+        let dir = temp_tree(&[("localization/english/a_l_english.yml", "l_english:\n")]);
+        assert_eq!(
+            resolve_spelling(&dir.path().join("localisation")),
+            dir.path().join("localization")
+        );
+    }
+
+    #[test]
+    fn test_detect_per_language_layout() {
+        let dir = temp_tree(&[("english/events/a_l_english.yml", "l_english:\n")]);
+        assert_eq!(
+            LocalisationLayout::detect(dir.path(), "english"),
+            LocalisationLayout::PerLanguage
+        );
+    }
+
+    #[test]
+    fn test_detect_flat_layout() {
+        let dir = temp_tree(FLAT);
+        let layout = LocalisationLayout::detect(dir.path(), "english");
+        assert_eq!(layout, LocalisationLayout::Flat);
+        assert_eq!(layout.language_dir(dir.path(), "english"), dir.path());
+    }
+
+    #[test]
+    fn test_flat_layout_files_of_one_language() {
+        let dir = temp_tree(FLAT);
+        let flat = dir.path();
+        assert_eq!(
+            LocalisationLayout::Flat
+                .language_files(flat, "english")
+                .unwrap(),
+            [
+                flat.join("a_l_english.yml"),
+                flat.join("replace/b_l_english.yaml")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flat_layout_needs_source_files() {
+        let dir = temp_tree(FLAT);
+        assert_eq!(
+            LocalisationLayout::detect(dir.path(), "french"),
+            LocalisationLayout::PerLanguage
+        );
+    }
+}
@@ -4,8 +4,14 @@
 
 mod client_settings;
 mod env;
+mod game;
+mod layout;
+mod output;
 mod task;
 
 pub use client_settings::*;
 pub use env::*;
+pub use game::*;
+pub use layout::*;
+pub use output::*;
 pub use task::*;
@@ -0,0 +1,286 @@
+//! 译文输出配置模块
+//!
+//! 决定译文写入的位置以及目标文件已存在时的处理方式。
+//! 默认写入 `{localisation_dir}/{lang}/replace/`，也可以写入普通目录、
+//! 单独的输出目录（如子 MOD 的本地化目录），或按路径模板写入。
+//! 源文件在源语言目录中的子目录会原样保留在输出目录中。
+//! 所有语言位于同一目录的布局中，语言目录即本地化目录本身。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::LocalisationLayout;
+
+/// 语言目录中的输出位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFolder {
+    /// `{lang}/`
+    Normal,
+    /// `{lang}/replace/`，其中的条目优先于其他文件中的同名条目
+    #[default]
+    Replace,
+}
+
+/// 译文的输出布局
+///
+/// 可以写为 `"normal"`、`"replace"`，`{ root = "...", folder = "replace" }`
+/// 或 `{ template = "{lang}/replace/{relpath}/{stem}.yml" }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OutputLayout {
+    /// 写入本地化目录中的语言目录
+    Folder(OutputFolder),
+    /// 写入单独的输出目录中的语言目录
+    Root {
+        root: PathBuf,
+        #[serde(default)]
+        folder: OutputFolder,
+    },
+    /// 按路径模板写入，相对路径相对于本地化目录
+    ///
+    /// `{lang}` 为目标语言，`{relpath}` 为源文件在源语言目录中所在的子目录，
+    /// `{stem}` 为不带扩展名的目标文件名
+    Template { template: String },
+}
+
+impl Default for OutputLayout {
+    fn default() -> Self {
+        OutputLayout::Folder(OutputFolder::default())
+    }
+}
+
+/// 模板中可用的占位符
+const TEMPLATE_PLACEHOLDERS: [&str; 3] = ["{lang}", "{relpath}", "{stem}"];
+
+impl OutputLayout {
+    /// 检查模板中的占位符
+    pub fn validate(&self) -> Result<(), crate::error::ConfigError> {
+        let OutputLayout::Template { template } = self else {
+            return Ok(());
+        };
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').map(|end| start + end + 1);
+            let placeholder = end.map(|end| &rest[start..end]);
+            if !placeholder.is_some_and(|p| TEMPLATE_PLACEHOLDERS.contains(&p)) {
+                return Err(crate::error::ConfigError::InvalidValue(format!(
+                    "output_layout.template 中有无法识别的占位符（可用 {}）: {}",
+                    TEMPLATE_PLACEHOLDERS.join("、"),
+                    template
+                )));
+            }
+            rest = &rest[end.unwrap_or(rest.len())..];
+        }
+        if !template.contains("{stem}") {
+            return Err(crate::error::ConfigError::InvalidValue(format!(
+                "output_layout.template 必须包含 {{stem}}: {}",
+                template
+            )));
+        }
+        Ok(())
+    }
+
+    /// 译文文件的路径
+    ///
+    /// `relpath` 为源文件在源语言目录中所在的子目录，`filename` 为目标文件名。
+    /// 模板中没有 `{relpath}` 时不保留子目录
+    pub fn output_path(
+        &self,
+        localisation_dir: &Path,
+        layout: LocalisationLayout,
+        lang: &str,
+        relpath: &Path,
+        filename: &str,
+    ) -> PathBuf {
+        match self {
+            OutputLayout::Folder(_) | OutputLayout::Root { .. } => self
+                .language_dir(localisation_dir, layout, lang)
+                .join(relpath)
+                .join(filename),
+            OutputLayout::Template { template } => {
+                let stem = Path::new(filename)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let rendered = template
+                    .replace("{lang}", lang)
+                    .replace("{relpath}", &relpath.to_string_lossy())
+                    .replace("{stem}", &stem);
+                // 子目录为空时去掉多余的分隔符
+                localisation_dir.join(Path::new(&rendered).components().collect::<PathBuf>())
+            }
+        }
+    }
+
+    /// 某一目标语言所有译文所在的目录
+    ///
+    /// 模板布局为模板中第一个 `{relpath}` 或 `{stem}` 之前的目录
+    pub fn output_tree(
+        &self,
+        localisation_dir: &Path,
+        layout: LocalisationLayout,
+        lang: &str,
+    ) -> PathBuf {
+        match self {
+            OutputLayout::Folder(_) => layout.language_dir(localisation_dir, lang),
+            OutputLayout::Root { root, .. } => layout.language_dir(root, lang),
+            OutputLayout::Template { template } => {
+                let rendered = template.replace("{lang}", lang);
+                let prefix = &rendered[..rendered.find('{').unwrap_or(rendered.len())];
+                let prefix = match prefix.rfind(['/', '\\']) {
+                    Some(end) => &prefix[..end],
+                    None => "",
+                };
+                localisation_dir.join(prefix)
+            }
+        }
+    }
+
+    /// 语言目录中写入译文的目录
+    fn language_dir(
+        &self,
+        localisation_dir: &Path,
+        layout: LocalisationLayout,
+        lang: &str,
+    ) -> PathBuf {
+        let (base, folder) = match self {
+            OutputLayout::Root { root, folder } => (layout.language_dir(root, lang), *folder),
+            OutputLayout::Folder(folder) => (layout.language_dir(localisation_dir, lang), *folder),
+            OutputLayout::Template { .. } => {
+                return self.output_tree(localisation_dir, layout, lang);
+            }
+        };
+        match folder {
+            OutputFolder::Normal => base,
+            OutputFolder::Replace => base.join("replace"),
+        }
+    }
+}
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExistingFilePolicy {
+    /// 重新翻译并覆盖
+    #[default]
+    Overwrite,
+    /// 跳过已存在的文件
+    Skip,
+    /// 停止任务并报错
+    Fail,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Config {
+        output_layout: OutputLayout,
+    }
+
+    const LOC: &str = "mod/localisation";
+    const EVENTS: &str = "events_l_simp_chinese.yml";
+
+    fn layout(toml_value: &str) -> OutputLayout {
+        toml::from_str::<Config>(&format!("output_layout = {}", toml_value))
+            .unwrap()
+            .output_layout
+    }
+
+    fn output_path(layout: &OutputLayout, files: LocalisationLayout, sub: &str) -> PathBuf {
+        layout.output_path(
+            Path::new(LOC),
+            files,
+            "simp_chinese",
+            Path::new(sub),
+            EVENTS,
+        )
+    }
+
+    #[test]
+    fn test_replace_layout_is_default() {
+        let replace = layout(r#""replace""#);
+        assert_eq!(replace, OutputLayout::default());
+        let loc = Path::new(LOC);
+        assert_eq!(
+            output_path(&replace, LocalisationLayout::PerLanguage, "events"),
+            loc.join("simp_chinese/replace/events").join(EVENTS)
+        );
+        assert_eq!(
+            output_path(&replace, LocalisationLayout::PerLanguage, ""),
+            loc.join("simp_chinese/replace").join(EVENTS)
+        );
+    }
+
+    #[test]
+    fn test_normal_layout() {
+        assert_eq!(
+            output_path(
+                &layout(r#""normal""#),
+                LocalisationLayout::PerLanguage,
+                "events"
+            ),
+            Path::new(LOC).join("simp_chinese/events").join(EVENTS)
+        );
+    }
+
+    #[test]
+    fn test_replace_layout_in_flat_folder() {
+        assert_eq!(
+            output_path(&OutputLayout::default(), LocalisationLayout::Flat, "events"),
+            Path::new(LOC).join("replace/events").join(EVENTS)
+        );
+    }
+
+    #[test]
+    fn test_root_layout() {
+        let root = layout(r#"{ root = "submod/localisation" }"#);
+        assert_eq!(
+            output_path(&root, LocalisationLayout::PerLanguage, "events"),
+            Path::new("submod/localisation/simp_chinese/replace/events").join(EVENTS)
+        );
+        assert_eq!(
+            root.output_tree(
+                Path::new(LOC),
+                LocalisationLayout::PerLanguage,
+                "simp_chinese"
+            ),
+            Path::new("submod/localisation/simp_chinese")
+        );
+    }
+
+    #[test]
+    fn test_template_layout() {
+        let template = layout(r#"{ template = "{lang}/replace/{relpath}/{stem}.yml" }"#);
+        assert!(template.validate().is_ok());
+        let loc = Path::new(LOC);
+        assert_eq!(
+            output_path(&template, LocalisationLayout::PerLanguage, "events"),
+            loc.join("simp_chinese/replace/events").join(EVENTS)
+        );
+        assert_eq!(
+            output_path(&template, LocalisationLayout::PerLanguage, ""),
+            loc.join("simp_chinese/replace").join(EVENTS)
+        );
+        assert_eq!(
+            template.output_tree(loc, LocalisationLayout::PerLanguage, "simp_chinese"),
+            loc.join("simp_chinese/replace")
+        );
+    }
+
+    #[test]
+    fn test_template_rejects_bad_placeholders() {
+        assert!(
+            layout(r#"{ template = "{lang}/{name}.yml" }"#)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            layout(r#"{ template = "{lang}/out.yml" }"#)
+                .validate()
+                .is_err()
+        );
+    }
+}
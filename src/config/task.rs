@@ -1,22 +1,247 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use crate::config::ClientSettings;
+use crate::config::{
+    ClientSettings, ExistingFilePolicy, Game, GameProfile, LocalisationLayout, OutputLayout,
+};
 
 /// 从TOML文件加载的翻译任务配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranslationTask {
+    /// 游戏（默认：stellaris），决定可用的标记、目录命名、支持的语言以及默认术语表
+    #[serde(default)]
+    pub game: Game,
+
     /// 源语言代码（例如："english"）
     pub source_lang: String,
 
     /// 目标语言代码列表（例如：["simp_chinese"]）
     pub target_langs: Vec<String>,
 
-    /// 使用的术语表名称（不带.json扩展名）
-    pub glossaries: Vec<String>,
+    /// 使用的术语表（名称不带.json扩展名），可以只写名称，也可以指定所属层级与优先级
+    ///
+    /// 省略时使用游戏配置中的默认术语表
+    #[serde(default)]
+    pub glossaries: Vec<GlossaryRef>,
 
     /// 本地化文件目录路径
+    ///
+    /// 目录不存在时会尝试 `localisation` 与 `localization` 的另一种拼写
     pub localisation_dir: PathBuf,
+
+    /// 本地化目录的布局（可选：per_language 或 flat），省略时按源语言目录是否存在自动检测
+    #[serde(default)]
+    pub localisation_layout: Option<LocalisationLayout>,
+
+    /// 译文的输出布局（默认：replace，即 `{localisation_dir}/{lang}/replace/`）
+    #[serde(default)]
+    pub output_layout: OutputLayout,
+
+    /// 目标文件已存在时的处理方式（默认：overwrite）
+    #[serde(default)]
+    pub existing_files: ExistingFilePolicy,
+
+    /// 备份目录（默认：.pmt_backups），覆盖译文文件前将原有的文件备份到其中以时间命名的子目录
+    ///
+    /// 相对路径相对于 MOD 根目录（见 [`TranslationTask::mod_root`]），可以用 `pmt restore` 恢复
+    #[serde(default = "default_backup_dir")]
+    pub backup_dir: PathBuf,
+
+    /// 状态文件（默认：.pmt_state.json），记录 pmt 写入的每个译文条目的哈希
+    ///
+    /// 相对路径相对于 MOD 根目录
+    /// 重新翻译时，值与记录不同（人工修改过）或上一行为 `# pmt:lock` 的条目会原样保留
+    #[serde(default = "default_state_file")]
+    pub state_file: PathBuf,
+
+    /// MOD 专属术语表名称（可选，位于 glossary_custom 中）
+    ///
+    /// 设置后，正式翻译前会先从源文本中提取 MOD 自创的名词单独翻译，
+    /// 结果保存到该术语表中，并在正式翻译时优先使用
+    #[serde(default)]
+    pub mod_glossary: Option<String>,
+
+    /// 合并多个术语表时发现冲突的处理策略（默认：custom_wins）
+    #[serde(default)]
+    pub glossary_conflict_policy: GlossaryConflictPolicy,
+
+    /// 禁止翻译的文本列表，`re:` 开头的为正则表达式，其余按字面匹配
+    ///
+    /// 与数据目录中的 do_not_translate.txt 合并使用
+    #[serde(default)]
+    pub do_not_translate: Vec<String>,
+
+    /// 是否将颜色、参数、图标、命令等标记替换为占位符后再发送给大模型（默认：否）
+    ///
+    /// 开启后大模型只能调整标记的位置，译文中的占位符没有恰好出现一次时会不替换标记重新翻译
+    #[serde(default)]
+    pub mask_markup: bool,
+
+    /// 是否在翻译后按原文修复可以机械修复的标记（默认：否）
+    ///
+    /// 如 `$能量$` 改回 `$energy$`、全角的 `￡` 改回 `£`、补上丢失的 `§!`
+    #[serde(default)]
+    pub fix_markup: bool,
+
+    /// 译文检查的设置
+    #[serde(default)]
+    pub validation: ValidationSettings,
+}
+
+fn default_backup_dir() -> PathBuf {
+    PathBuf::from(".pmt_backups")
+}
+
+fn default_state_file() -> PathBuf {
+    PathBuf::from(".pmt_state.json")
+}
+
+/// 译文检查的设置，对应任务中的 `[task.validation]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationSettings {
+    /// 允许与原文相同的文本（如缩写、型号），不报告为未翻译
+    pub allow_untranslated: Vec<String>,
+    /// 译文中属于目标语言文字的字母所占的最低比例，低于该比例报告为语言错误
+    pub min_script_ratio: f64,
+    /// 各目标语言译文与原文的期望长度比（去除标记后的字符数之比），未配置的语言使用内置值
+    pub length_ratios: HashMap<String, f64>,
+    /// 实际长度比超过期望值的该倍数或低于其该分之一时，报告为长度异常
+    pub length_tolerance: f64,
+    /// 原文去除标记后少于该字符数的条目不检查长度
+    pub min_length: usize,
+    /// 以逗号作为小数点、以句点或空格作为千位分隔符的语言
+    pub decimal_comma_langs: Vec<String>,
+    /// 比较数字时是否将全角数字视为半角数字
+    pub fullwidth_digits: bool,
+}
+
+impl Default for ValidationSettings {
+    fn default() -> Self {
+        Self {
+            allow_untranslated: Vec::new(),
+            min_script_ratio: 0.5,
+            length_ratios: HashMap::new(),
+            length_tolerance: 2.5,
+            min_length: 20,
+            decimal_comma_langs: [
+                "french", "german", "spanish", "braz_por", "polish", "russian", "turkish",
+            ]
+            .map(String::from)
+            .to_vec(),
+            fullwidth_digits: true,
+        }
+    }
+}
+
+/// 术语表所属的层级，层级越具体，默认优先级越高
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlossaryScope {
+    /// 游戏本体
+    #[default]
+    Game,
+    /// DLC
+    Dlc,
+    /// 一系列相关的 MOD 共用
+    ModFamily,
+    /// 单个 MOD
+    Mod,
+}
+
+impl GlossaryScope {
+    /// 该层级的默认优先级
+    pub fn default_priority(self) -> i32 {
+        match self {
+            GlossaryScope::Game => 0,
+            GlossaryScope::Dlc => 100,
+            GlossaryScope::ModFamily => 200,
+            GlossaryScope::Mod => 300,
+        }
+    }
+}
+
+impl std::fmt::Display for GlossaryScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            GlossaryScope::Game => "game",
+            GlossaryScope::Dlc => "dlc",
+            GlossaryScope::ModFamily => "mod_family",
+            GlossaryScope::Mod => "mod",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 任务中引用的术语表
+///
+/// 可以只写名称（视为 game 层级），也可以写为 `{ name = "...", scope = "mod_family", priority = 250 }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GlossaryRef {
+    Name(String),
+    Layer {
+        name: String,
+        #[serde(default)]
+        scope: GlossaryScope,
+        /// 优先级，数值大的覆盖数值小的；省略时使用层级的默认优先级
+        #[serde(default)]
+        priority: Option<i32>,
+    },
+}
+
+impl GlossaryRef {
+    /// 术语表名称
+    pub fn name(&self) -> &str {
+        match self {
+            GlossaryRef::Name(name) => name,
+            GlossaryRef::Layer { name, .. } => name,
+        }
+    }
+
+    /// 术语表所属层级
+    pub fn scope(&self) -> GlossaryScope {
+        match self {
+            GlossaryRef::Name(_) => GlossaryScope::Game,
+            GlossaryRef::Layer { scope, .. } => *scope,
+        }
+    }
+
+    /// 术语表优先级
+    pub fn priority(&self) -> i32 {
+        match self {
+            GlossaryRef::Name(_) => GlossaryScope::Game.default_priority(),
+            GlossaryRef::Layer {
+                scope, priority, ..
+            } => priority.unwrap_or_else(|| scope.default_priority()),
+        }
+    }
+}
+
+impl std::fmt::Display for GlossaryRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, priority {})",
+            self.name(),
+            self.scope(),
+            self.priority()
+        )
+    }
+}
+
+/// 术语表冲突的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlossaryConflictPolicy {
+    /// 后加载的术语表优先（自定义术语表覆盖默认术语表），冲突仅作为警告报告
+    #[default]
+    CustomWins,
+    /// 发现冲突时终止任务
+    Fail,
+    /// 保留优先的译法，并将冲突的译法作为其他可接受的译法一并提供给大模型
+    Alternatives,
 }
 
 /// 完整的任务配置文件结构
@@ -35,9 +260,29 @@ impl TaskFileConfig {
         let content = std::fs::read_to_string(path)
             .map_err(|e| crate::error::ConfigError::InvalidPath(e.to_string()))?;
 
-        let config: TaskFileConfig =
+        let mut config: TaskFileConfig =
             toml::from_str(&content).map_err(crate::error::ConfigError::TomlParse)?;
 
+        for task in &mut config.task {
+            let resolved = crate::config::resolve_spelling(&task.localisation_dir);
+            if resolved != task.localisation_dir {
+                log::info!(
+                    "本地化目录 {:?} 不存在，使用 {:?}",
+                    task.localisation_dir,
+                    resolved
+                );
+                task.localisation_dir = resolved;
+            }
+            // 自动检测需要遍历本地化目录，加载时只检测一次
+            if task.localisation_layout.is_none() {
+                task.localisation_layout = Some(task.layout());
+            }
+            // 备份与状态文件跟随 MOD 存放，不受运行目录影响
+            let root = task.mod_root();
+            task.backup_dir = root.join(&task.backup_dir);
+            task.state_file = root.join(&task.state_file);
+        }
+
         // 验证客户端设置
         config.client_settings.validate()?;
 
@@ -66,6 +311,14 @@ impl TranslationTask {
         Ok((config.client_settings, config.task))
     }
 
+    /// MOD 根目录，即本地化目录的上一级目录
+    pub fn mod_root(&self) -> PathBuf {
+        self.localisation_dir
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
     /// 验证配置
     pub fn validate(&self) -> Result<(), crate::error::ConfigError> {
         if self.source_lang.is_empty() {
@@ -80,6 +333,58 @@ impl TranslationTask {
             ));
         }
 
+        let profile = self.profile();
+        for lang in std::iter::once(&self.source_lang).chain(&self.target_langs) {
+            // 游戏不原生支持的语言仍可能由 MOD 自行添加，只给出警告
+            if !profile.supports_language(lang) {
+                log::warn!(
+                    "{} 不原生支持语言 {}，可用的语言：{}",
+                    self.game,
+                    lang,
+                    profile.languages.join(", ")
+                );
+            }
+        }
+
+        if let Some(folder) = self.localisation_dir.file_name()
+            && (folder == "localisation" || folder == "localization")
+            && folder != profile.localisation_folder
+        {
+            log::warn!(
+                "{} 的本地化目录名为 {}，但配置的是 {:?}",
+                self.game,
+                profile.localisation_folder,
+                self.localisation_dir
+            );
+        }
+
+        if !(0.0..=1.0).contains(&self.validation.min_script_ratio) {
+            return Err(crate::error::ConfigError::InvalidValue(format!(
+                "validation.min_script_ratio 必须在 0 到 1 之间: {}",
+                self.validation.min_script_ratio
+            )));
+        }
+
+        self.output_layout.validate()?;
+
+        if self.validation.length_tolerance <= 1.0 {
+            return Err(crate::error::ConfigError::InvalidValue(format!(
+                "validation.length_tolerance 必须大于 1: {}",
+                self.validation.length_tolerance
+            )));
+        }
+        if let Some((lang, ratio)) = self
+            .validation
+            .length_ratios
+            .iter()
+            .find(|(_, ratio)| **ratio <= 0.0)
+        {
+            return Err(crate::error::ConfigError::InvalidValue(format!(
+                "validation.length_ratios.{} 必须大于 0: {}",
+                lang, ratio
+            )));
+        }
+
         if !self.localisation_dir.exists() {
             return Err(crate::error::ConfigError::InvalidPath(format!(
                 "本地化目录不存在: {:?}",
@@ -88,24 +393,175 @@ impl TranslationTask {
         }
 
         // 检查源语言目录是否存在
-        let source_dir = self.localisation_dir.join(&self.source_lang);
+        let source_dir = self.source_dir();
         if !source_dir.exists() {
             return Err(crate::error::ConfigError::InvalidPath(format!(
-                "源语言目录不存在: {:?}",
-                source_dir
+                "源语言目录不存在，本地化目录中也没有 *_l_{}.yml 文件: {:?}",
+                self.source_lang, source_dir
             )));
         }
 
         Ok(())
     }
 
+    /// 任务所用游戏的配置
+    pub fn profile(&self) -> GameProfile {
+        self.game.profile()
+    }
+
+    /// 本地化目录的布局，未配置时自动检测（从任务文件加载的任务已在加载时检测）
+    pub fn layout(&self) -> LocalisationLayout {
+        self.localisation_layout.unwrap_or_else(|| {
+            LocalisationLayout::detect(&self.localisation_dir, &self.source_lang)
+        })
+    }
+
     /// 获取源语言目录路径
     pub fn source_dir(&self) -> PathBuf {
-        self.localisation_dir.join(&self.source_lang)
+        self.layout()
+            .language_dir(&self.localisation_dir, &self.source_lang)
+    }
+
+    /// 源语言的所有本地化文件，按路径排序
+    pub fn source_files(&self) -> crate::error::Result<Vec<PathBuf>> {
+        self.layout()
+            .language_files(&self.source_dir(), &self.source_lang)
+    }
+
+    /// 输出目录中已有的某目标语言的本地化文件，按路径排序
+    pub fn target_files(&self, target_lang: &str) -> crate::error::Result<Vec<PathBuf>> {
+        self.layout()
+            .language_files(&self.output_tree(target_lang), target_lang)
+    }
+
+    /// 源文件对应的译文文件路径
+    pub fn output_path(&self, target_lang: &str, source_file: &Path) -> PathBuf {
+        let relpath = source_file
+            .strip_prefix(self.source_dir())
+            .ok()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        let filename = source_file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let filename =
+            crate::preprocess::generate_target_filename(&filename, &self.source_lang, target_lang);
+        self.output_layout.output_path(
+            &self.localisation_dir,
+            self.layout(),
+            target_lang,
+            relpath,
+            &filename,
+        )
+    }
+
+    /// 所有源文件对应的译文文件路径，顺序与 `source_files` 相同
+    ///
+    /// 不同的源文件写入同一个译文文件时（如 `a_l_english.yml` 与 `a_l_english.yaml`，
+    /// 或模板中没有 `{relpath}` 而不同子目录中有同名文件）返回错误
+    pub fn output_paths(
+        &self,
+        target_lang: &str,
+        source_files: &[PathBuf],
+    ) -> Result<Vec<PathBuf>, crate::error::ConfigError> {
+        let mut sources: HashMap<PathBuf, &Path> = HashMap::new();
+        let mut paths = Vec::with_capacity(source_files.len());
+        for source_file in source_files {
+            let output_path = self.output_path(target_lang, source_file);
+            if let Some(other) = sources.insert(output_path.clone(), source_file) {
+                return Err(crate::error::ConfigError::InvalidValue(format!(
+                    "源文件 {:?} 与 {:?} 的译文都会写入 {:?}",
+                    other, source_file, output_path
+                )));
+            }
+            paths.push(output_path);
+        }
+        Ok(paths)
+    }
+
+    /// 特定目标语言的所有译文所在的目录
+    pub fn output_tree(&self, target_lang: &str) -> PathBuf {
+        self.output_layout
+            .output_tree(&self.localisation_dir, self.layout(), target_lang)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_tree, write_file};
+
+    fn task(extra: &str) -> TranslationTask {
+        toml::from_str(&format!(
+            "source_lang = \"english\"\ntarget_langs = [\"simp_chinese\"]\nlocalisation_dir = \"loc\"\n{}",
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_output_paths_mirror_source_tree() {
+        let sources = [
+            PathBuf::from("loc/english/events/a_l_english.yml"),
+            PathBuf::from("loc/english/tech/a_l_english.yml"),
+        ];
+        assert_eq!(
+            task("").output_paths("simp_chinese", &sources).unwrap(),
+            [
+                PathBuf::from("loc/simp_chinese/replace/events/a_l_simp_chinese.yml"),
+                PathBuf::from("loc/simp_chinese/replace/tech/a_l_simp_chinese.yml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_paths_reject_collisions() {
+        let sources = [
+            PathBuf::from("loc/english/events/a_l_english.yml"),
+            PathBuf::from("loc/english/tech/a_l_english.yml"),
+        ];
+        let flat = task("output_layout = { template = \"{lang}/{stem}.yml\" }");
+        assert!(flat.output_paths("simp_chinese", &sources).is_err());
+    }
+
+    #[test]
+    fn test_output_paths_reject_yml_and_yaml_pair() {
+        let sources = [
+            PathBuf::from("loc/english/a_l_english.yml"),
+            PathBuf::from("loc/english/a_l_english.yaml"),
+        ];
+        assert!(task("").output_paths("simp_chinese", &sources).is_err());
+    }
+
+    #[test]
+    fn test_layout_detected_on_load() {
+        let dir = temp_tree(&[("my_mod/localisation/a_l_english.yml", "l_english:\n")]);
+        let content = format!(
+            "[[task]]\nsource_lang = \"english\"\ntarget_langs = [\"simp_chinese\"]\n\
+             localisation_dir = {:?}\n",
+            dir.path().join("my_mod/localisation")
+        );
+        write_file(dir.path(), "task.toml", &content);
+        let (_, tasks) = TranslationTask::from_file(dir.path().join("task.toml")).unwrap();
+        assert_eq!(tasks[0].localisation_layout, Some(LocalisationLayout::Flat));
     }
 
-    /// 获取特定目标语言的目标目录路径
-    pub fn target_dir(&self, target_lang: &str) -> PathBuf {
-        self.localisation_dir.join(target_lang).join("replace")
+    #[test]
+    fn test_backup_and_state_follow_mod_root() {
+        let dir = temp_tree(&[(
+            "my_mod/localisation/english/a_l_english.yml",
+            "l_english:\n",
+        )]);
+        let content = format!(
+            "[[task]]\nsource_lang = \"english\"\ntarget_langs = [\"simp_chinese\"]\n\
+             localisation_dir = {:?}\nbackup_dir = \"backups\"\n",
+            dir.path().join("my_mod/localisation")
+        );
+        write_file(dir.path(), "task.toml", &content);
+        let (_, tasks) = TranslationTask::from_file(dir.path().join("task.toml")).unwrap();
+        let root = dir.path().join("my_mod");
+        assert_eq!(tasks[0].backup_dir, root.join("backups"));
+        assert_eq!(tasks[0].state_file, root.join(".pmt_state.json"));
     }
 }
@@ -1,8 +1,11 @@
 //! Paradox Mod Translator - AI-powered translation tool for Paradox game mods.
 
+pub mod commands;
 pub mod config;
 pub mod postprocess;
 pub mod preprocess;
+#[cfg(test)]
+mod test_util;
 pub mod translate;
 pub mod utils;
 
@@ -13,7 +16,7 @@ pub use error::{Result, TranslationError};
 
 use crate::{
     preprocess::{fix_yaml_content, trim_lang_header},
-    translate::{FileChunk, FormatValidator},
+    translate::{FileChunk, Validators},
 };
 
 /// 执行翻译任务
@@ -22,9 +25,8 @@ pub async fn translate_task(
     client_settings: config::ClientSettings,
     concurrent: bool,
 ) -> Result<()> {
-    use crate::translate::{Translator, load_glossaries_from_task};
-    use std::fs;
-    use walkdir::WalkDir;
+    use crate::config::ExistingFilePolicy;
+    use crate::translate::{DoNotTranslate, KeyIndex, Translator, load_glossaries_from_task};
 
     log::info!("Starting translation task");
     log::info!("Source language: {}", task.source_lang);
@@ -36,113 +38,286 @@ pub async fn translate_task(
     // 2. 创建翻译器
     let max_chunk_tokens = client_settings.max_chunk_tokens;
     let concurrency = client_settings.concurrency;
-    let translator = Translator::from_settings(client_settings, merged_glossary)?;
+    let mut translator = Translator::from_settings(client_settings, merged_glossary)?;
+    translator.set_game(task.game);
+    translator.set_validation(task.validation.clone());
+    translator.set_mask_markup(task.mask_markup);
+    translator.set_fix_markup(task.fix_markup);
 
     // 3. 遍历源目录中的文件
     let source_dir = task.source_dir();
     log::info!("Reading source files from: {:?}", source_dir);
 
-    let mut source_files = Vec::new();
-    for entry in WalkDir::new(&source_dir) {
-        let entry = entry.map_err(|e| {
-            TranslationError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("WalkDir error: {}", e),
-            ))
-        })?;
-        if entry.file_type().is_file() {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "yml" || ext == "yaml" {
-                    source_files.push(path.to_path_buf());
-                }
-            }
+    let source_files = task.source_files()?;
+
+    log::info!("Found {} source files", source_files.len());
+
+    // 译文保留源文件的子目录，翻译前确认不同的源文件不会写入同一个译文文件；
+    // existing_files 为 fail 时也在翻译开始前检查，避免写入一半后才报错
+    let mut output_paths = Vec::with_capacity(task.target_langs.len());
+    for target_lang in &task.target_langs {
+        let paths = task.output_paths(target_lang, &source_files)?;
+        if task.existing_files == ExistingFilePolicy::Fail
+            && let Some(existing) = paths.iter().find(|path| path.exists())
+        {
+            return Err(TranslationError::Postprocess(
+                crate::error::PostprocessError::WriteFailed(format!(
+                    "Target file already exists: {:?}",
+                    existing
+                )),
+            ));
         }
+        output_paths.push(paths);
     }
 
-    log::info!("Found {} source files", source_files.len());
+    // 建立键索引，用于在提示词中展开 $key$ 引用
+    let mut key_index = KeyIndex::from_files(&source_files)?;
+    // 引用原版或其他 MOD 的键很常见，只汇总数量，逐条列出放在 debug 级别
+    let missing = key_index.missing_references();
+    for (from, key) in &missing {
+        log::debug!(
+            "Key '{}' references '${}$', which is not defined in the source files",
+            from,
+            key
+        );
+    }
+    if !missing.is_empty() {
+        log::info!(
+            "{} key references point outside the source files (vanilla or other mods); their text is not added to prompts",
+            missing.len()
+        );
+    }
+    for target_lang in &task.target_langs {
+        let loaded = key_index.load_translations(target_lang, &task.target_files(target_lang)?)?;
+        if loaded > 0 {
+            log::info!(
+                "Loaded {} existing {} translations for key references",
+                loaded,
+                target_lang
+            );
+        }
+    }
+    translator.set_key_index(key_index);
+
+    // 加载禁止翻译列表
+    let do_not_translate = DoNotTranslate::load(&task)?;
+    translator.set_do_not_translate(do_not_translate.clone());
+
+    // 4. 预翻译 MOD 自创的术语，保证后续译文前后一致
+    if let Some(name) = &task.mod_glossary {
+        bootstrap_mod_glossary(
+            &mut translator,
+            &task,
+            name,
+            &source_files,
+            &do_not_translate,
+        )
+        .await?;
+    }
 
     let total = task.target_langs.len() * source_files.len();
     let mut count = 0;
-    // 4. 对每个目标语言进行翻译
-    for target_lang in &task.target_langs {
+    let mut writer = postprocess::TargetWriter::new(&task.backup_dir, &task.state_file)?;
+    let (mut edited, mut locked) = (0, 0);
+    // 5. 对每个目标语言进行翻译
+    for (target_lang, output_paths) in task.target_langs.iter().zip(&output_paths) {
         log::info!("Translating to: {}", target_lang);
 
-        let target_dir = task.target_dir(target_lang);
-        log::info!("Output directory: {:?}", target_dir);
+        log::info!("Output directory: {:?}", task.output_tree(target_lang));
 
-        // 创建目标目录
-        fs::create_dir_all(&target_dir)?;
-
-        for source_file in &source_files {
+        for (source_file, output_path) in source_files.iter().zip(output_paths) {
             log::info!("Processing file: {:?}", source_file);
-            if concurrent {
+            if task.existing_files == ExistingFilePolicy::Skip && output_path.exists() {
+                log::info!("Skipping existing file: {:?}", output_path);
+                count += 1;
+                continue;
+            }
+            // 已有译文中人工修改或锁定的条目原样保留，不再翻译
+            let kept = match std::fs::read_to_string(output_path) {
+                Ok(existing) => {
+                    postprocess::KeptEntries::find(&existing, writer.hashes(output_path))
+                }
+                Err(_) => postprocess::KeptEntries::default(),
+            };
+            if !kept.is_empty() {
+                log::info!(
+                    "Keeping {} human-edited and {} locked entries in {:?}",
+                    kept.edited,
+                    kept.locked,
+                    output_path
+                );
+            }
+            let source = std::fs::read_to_string(source_file)?;
+            for key in kept.removed_from(&source) {
+                log::warn!(
+                    "Kept entry '{}' in {:?} is no longer in the source file, remove it if it is obsolete",
+                    key,
+                    output_path
+                );
+            }
+            for key in kept.changed_in(&source, writer.source_hashes(output_path)) {
+                log::warn!(
+                    "Source text of kept entry '{}' in {:?} changed since it was last translated, it may be out of date",
+                    key,
+                    output_path
+                );
+            }
+            let remaining = kept.remove_from(&source);
+            let translated = if preprocess::parse_entries(&remaining).is_empty() {
+                format!("l_{}:\n", target_lang)
+            } else if concurrent {
                 translate_one_file_batch(
                     &translator,
                     &task.source_lang,
                     target_lang,
                     max_chunk_tokens,
                     concurrency,
-                    &target_dir,
-                    source_file,
+                    output_path,
+                    &remaining,
                 )
-                .await?;
+                .await?
             } else {
                 translate_one_file(
                     &translator,
                     &task.source_lang,
                     target_lang,
                     max_chunk_tokens,
-                    &target_dir,
-                    source_file,
+                    output_path,
+                    &remaining,
                 )
-                .await?;
-            }
+                .await?
+            };
+            let content = kept.merge_into(&translated, &source);
+            writer.write(&content, output_path, &source)?;
+            translator.record_translations(target_lang, &content);
+            log::info!("Successfully translated: {:?}", output_path);
+            edited += kept.edited;
+            locked += kept.locked;
             count += 1;
             log::info!("Progress: {}/{} files translated", count, total);
         }
     }
 
+    log::info!(
+        "Kept {} human-edited and {} locked entries from existing translations",
+        edited,
+        locked
+    );
+    if let Some(id) = writer.backup_id() {
+        log::info!(
+            "Previous versions were backed up as run {}, use `pmt restore` to roll back",
+            id
+        );
+    }
     log::info!("Translation task completed successfully!");
     Ok(())
 }
 
+/// 从源文件中提取候选术语并单独翻译，保存到 MOD 专属术语表并并入翻译器
+///
+/// 术语表中已有译法的术语以及禁止翻译的文本不会被翻译
+pub async fn bootstrap_mod_glossary(
+    translator: &mut translate::Translator,
+    task: &config::TranslationTask,
+    name: &str,
+    source_files: &[std::path::PathBuf],
+    do_not_translate: &translate::DoNotTranslate,
+) -> Result<()> {
+    use crate::preprocess::read_entries;
+    use crate::translate::{Glossary, TermExtractor};
+    use crate::utils::data_file_for_write;
+
+    let mut entries = Vec::new();
+    for source_file in source_files {
+        entries.extend(read_entries(source_file)?);
+    }
+
+    let path = data_file_for_write(&format!("glossary_custom/{}.json", name))?;
+    let mut mod_glossary = if path.exists() {
+        Glossary::from_json_file_strict(&path)?
+    } else {
+        Glossary::default()
+    };
+
+    let extractor = TermExtractor::new();
+    let mut added = 0;
+    for target_lang in &task.target_langs {
+        let candidates: Vec<_> = extractor
+            .extract(
+                &entries,
+                translator.glossary(),
+                &task.source_lang,
+                target_lang,
+            )
+            .into_iter()
+            .filter(|c| !do_not_translate.is_protected(&c.term))
+            .collect();
+        if candidates.is_empty() {
+            log::info!("No new terms to translate for {}", target_lang);
+            continue;
+        }
+        log::info!(
+            "Extracted {} candidate terms for {}",
+            candidates.len(),
+            target_lang
+        );
+        let terms: Vec<String> = candidates.into_iter().map(|c| c.term).collect();
+        let translated = translator
+            .translate_terms(&terms, &task.source_lang, target_lang)
+            .await?;
+        for (term, translation) in translated {
+            let key = term.to_lowercase();
+            let mut item = mod_glossary.get(&key).cloned().unwrap_or_default();
+            item.set(&task.source_lang, Some(key.clone()))?;
+            item.set(target_lang, Some(translation))?;
+            mod_glossary.insert(key, item)?;
+            added += 1;
+        }
+    }
+
+    if added == 0 {
+        return Ok(());
+    }
+    mod_glossary.save_json_file(&path)?;
+    log::info!(
+        "Saved {} new terms to mod glossary {}",
+        added,
+        path.display()
+    );
+    translator.extend_glossary(
+        &mod_glossary,
+        &task.source_lang,
+        task.glossary_conflict_policy,
+    )?;
+    Ok(())
+}
+
+/// 翻译一个源文件的内容，返回译文；`output_path` 为译文文件的路径
 pub async fn translate_one_file(
     translator: &translate::Translator,
     source_lang: &str,
     target_lang: &str,
     max_chunk_tokens: usize,
-    target_dir: &std::path::PathBuf,
-    source_file: &std::path::PathBuf,
-) -> Result<()> {
-    use crate::postprocess::{reconstruct_yaml_file, write_translated_file};
-    use crate::preprocess::{fix_yaml_content, generate_target_filename, trim_lang_header};
+    output_path: &std::path::Path,
+    source: &str,
+) -> Result<String> {
+    use crate::postprocess::reconstruct_yaml_file;
+    use crate::preprocess::{fix_yaml_content, trim_lang_header};
     use crate::translate::split_yaml_content;
-    use std::fs;
 
-    // 算出输出文件路径
-    let filename = source_file
+    let target_filename = output_path
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| TranslationError::FileNotFound("Invalid filename".to_string()))?;
-    let target_filename = generate_target_filename(filename, &source_lang, target_lang);
-    let output_path = target_dir.join(&target_filename);
 
-    // 读取源文件内容
-    let content = fs::read_to_string(source_file)?;
     // 去除 BOM 头
-    let content = if content.starts_with("\u{FEFF}") {
-        content.trim_start_matches("\u{FEFF}")
-    } else {
-        &content
-    }
-    .to_string();
+    let content = source.trim_start_matches('\u{FEFF}').to_string();
     // 去除语言头标记
-    let (_original_header, content) = trim_lang_header(&source_lang, &content);
+    let (_original_header, content) = trim_lang_header(source_lang, &content);
     // 修复YAML文件中的格式问题
     let content = fix_yaml_content(&content)?;
     // 切片
-    let chunks = split_yaml_content(&target_filename, &content, max_chunk_tokens)?;
+    let chunks = split_yaml_content(target_filename, &content, max_chunk_tokens)?;
     log::info!("File split into {} chunks", chunks.len());
 
     // 翻译每个切片
@@ -154,7 +329,7 @@ pub async fn translate_one_file(
         );
 
         let slice = translator
-            .translate_chunk(&chunk, &source_lang, target_lang)
+            .translate_chunk(chunk, source_lang, target_lang)
             .await?;
 
         log::trace!(
@@ -165,50 +340,37 @@ pub async fn translate_one_file(
         translated_chunks.push(slice);
         log::info!("Translated chunk {}/{}", i + 1, chunks.len());
     }
-    let reconstructed = reconstruct_yaml_file(translated_chunks, &target_lang)?;
-
-    write_translated_file(&reconstructed, &output_path, true)?;
-    log::info!("Successfully translated: {:?}", output_path);
-    Ok(())
+    let reconstructed = reconstruct_yaml_file(translated_chunks, target_lang)?;
+    Ok(reconstructed)
 }
 
+/// 按批次并发翻译一个源文件的内容，返回译文；`output_path` 为译文文件的路径
 pub async fn translate_one_file_batch(
     translator: &translate::Translator,
     source_lang: &str,
     target_lang: &str,
     max_chunk_tokens: usize,
     batch_size: usize,
-    target_dir: &std::path::PathBuf,
-    source_file: &std::path::PathBuf,
-) -> Result<()> {
-    use crate::postprocess::{reconstruct_yaml_file, write_translated_file};
-    use crate::preprocess::{fix_yaml_content, generate_target_filename, trim_lang_header};
+    output_path: &std::path::Path,
+    source: &str,
+) -> Result<String> {
+    use crate::postprocess::reconstruct_yaml_file;
+    use crate::preprocess::{fix_yaml_content, trim_lang_header};
     use crate::translate::split_yaml_content;
-    use std::fs;
 
-    // 算出输出文件路径
-    let filename = source_file
+    let target_filename = output_path
         .file_name()
         .and_then(|n| n.to_str())
         .ok_or_else(|| TranslationError::FileNotFound("Invalid filename".to_string()))?;
-    let target_filename = generate_target_filename(filename, &source_lang, target_lang);
-    let output_path = target_dir.join(&target_filename);
 
-    // 读取源文件内容
-    let content = fs::read_to_string(source_file)?;
     // 去除 BOM 头
-    let content = if content.starts_with("\u{FEFF}") {
-        content.trim_start_matches("\u{FEFF}")
-    } else {
-        &content
-    }
-    .to_string();
+    let content = source.trim_start_matches('\u{FEFF}').to_string();
     // 去除语言头标记
-    let (_original_header, content) = trim_lang_header(&source_lang, &content);
+    let (_original_header, content) = trim_lang_header(source_lang, &content);
     // 修复YAML文件中的格式问题
     let content = fix_yaml_content(&content)?;
     // 切片
-    let chunks = split_yaml_content(&target_filename, &content, max_chunk_tokens)?;
+    let chunks = split_yaml_content(target_filename, &content, max_chunk_tokens)?;
     log::info!("File split into {} chunks", chunks.len());
 
     // 翻译每个切片
@@ -217,24 +379,21 @@ pub async fn translate_one_file_batch(
     let batches = chunks.chunks(batch_size);
     let mut translated_count = 0;
     for batch in batches {
-        let chunks: Vec<FileChunk> = batch.into_iter().map(|x| x.to_owned()).collect();
+        let chunks: Vec<FileChunk> = batch.iter().map(|x| x.to_owned()).collect();
         let will_translate = chunks.len();
         let slices = translator
-            .translate_batch(chunks, &source_lang, target_lang)
+            .translate_batch(chunks, source_lang, target_lang)
             .await?;
         translated_count += will_translate;
         log::info!("Translated chunk {}/{}", translated_count, total);
         translated_slices.extend(slices);
     }
-    let reconstructed = reconstruct_yaml_file(translated_slices, &target_lang)?;
-
-    write_translated_file(&reconstructed, &output_path, true)?;
-    log::info!("Successfully translated: {:?}", output_path);
-    Ok(())
+    let reconstructed = reconstruct_yaml_file(translated_slices, target_lang)?;
+    Ok(reconstructed)
 }
 
-pub async fn validate_translation(task: config::TranslationTask) -> Result<()> {
-    use walkdir::WalkDir;
+pub async fn validate_translation(task: config::TranslationTask, fix: bool) -> Result<()> {
+    use crate::translate::{DoNotTranslate, load_glossaries_from_task};
 
     log::info!("Starting translation validation");
     log::info!("Source language: {}", task.source_lang);
@@ -243,47 +402,55 @@ pub async fn validate_translation(task: config::TranslationTask) -> Result<()> {
     let source_dir = task.source_dir();
     log::info!("Reading source files from: {:?}", source_dir);
 
-    let mut source_files = Vec::new();
-    for entry in WalkDir::new(&source_dir) {
-        let entry = entry.map_err(|e| {
-            TranslationError::Io(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("WalkDir error: {}", e),
-            ))
-        })?;
-        if entry.file_type().is_file() {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "yml" || ext == "yaml" {
-                    source_files.push(path.to_path_buf());
-                }
-            }
-        }
-    }
+    let source_files = task.source_files()?;
 
     log::info!("Found {} source files", source_files.len());
+    validate_duplicate_keys(&source_dir, &source_files)?;
+
+    // 加载术语表，用于检查译文是否遵守术语
+    let glossary = load_glossaries_from_task(&task)?;
+    // 加载禁止翻译列表，用于检查受保护的文本是否被原样保留
+    let do_not_translate = DoNotTranslate::load(&task)?;
+    // --fix 改写译文文件前先备份，并记录写入的条目
+    let mut writer = postprocess::TargetWriter::new(&task.backup_dir, &task.state_file)?;
 
     for target_lang in &task.target_langs {
         log::info!(
             "Validating translations for target language: {}",
             target_lang
         );
+        // 按游戏的标记语法检查格式，并检查术语、禁止翻译的文本与目标语言
+        let validators = Validators::new(
+            &glossary,
+            &do_not_translate,
+            task.profile().markup,
+            &task.validation,
+            &task.source_lang,
+            target_lang,
+        );
 
-        let target_dir = task.target_dir(target_lang);
-        log::info!("Looking for translated files in: {:?}", target_dir);
+        let output_paths = task.output_paths(target_lang, &source_files)?;
+        let language_dir = task.output_tree(target_lang);
+        if language_dir.exists() {
+            let target_files = task.target_files(target_lang)?;
+            validate_duplicate_keys(&language_dir, &target_files)?;
+            validate_language_files(&language_dir, &target_files, target_lang, &output_paths)?;
+        }
 
-        for source_file in &source_files {
-            let filename = source_file
-                .file_name()
-                .and_then(|n| n.to_str())
-                .ok_or_else(|| TranslationError::FileNotFound("Invalid filename".to_string()))?;
-            let target_filename =
-                preprocess::generate_target_filename(filename, &task.source_lang, target_lang);
-            let output_path = target_dir.join(&target_filename);
+        log::info!("Looking for translated files in: {:?}", language_dir);
 
+        for (source_file, output_path) in source_files.iter().zip(&output_paths) {
             if output_path.exists() {
-                validate_one_file(&task.source_lang, target_lang, source_file, &output_path)
-                    .await?;
+                validate_one_file(
+                    &task.source_lang,
+                    target_lang,
+                    source_file,
+                    output_path,
+                    &validators,
+                    fix,
+                    &mut writer,
+                )
+                .await?;
             } else {
                 log::warn!("Missing translated file: {:?}", output_path);
             }
@@ -294,16 +461,73 @@ pub async fn validate_translation(task: config::TranslationTask) -> Result<()> {
     Ok(())
 }
 
+/// 检查整个语言目录中重复定义的键，并报告游戏实际使用的定义
+pub fn validate_duplicate_keys(dir: &std::path::Path, files: &[std::path::PathBuf]) -> Result<()> {
+    let duplicates = translate::find_duplicate_keys(dir, files)?;
+    if duplicates.is_empty() {
+        log::info!("[x] No duplicate keys in {}", dir.display());
+        return Ok(());
+    }
+    log::warn!("[ ] Duplicate keys in {}:", dir.display());
+    for (i, duplicate) in duplicates.iter().enumerate() {
+        log::warn!("  {}. {}", i + 1, duplicate);
+    }
+    Ok(())
+}
+
+/// 检查目标语言目录中每个文件的 BOM、语言头与文件名，以及没有对应源文件的多余文件
+pub fn validate_language_files(
+    dir: &std::path::Path,
+    files: &[std::path::PathBuf],
+    target_lang: &str,
+    output_paths: &[std::path::PathBuf],
+) -> Result<()> {
+    let problems = translate::check_language_files(dir, files, target_lang, output_paths)?;
+    if problems.is_empty() {
+        log::info!("[x] All files in {} are well-formed", dir.display());
+        return Ok(());
+    }
+    log::warn!("[ ] File problems in {}:", dir.display());
+    for (i, problem) in problems.iter().enumerate() {
+        log::warn!("  {}. {}", i + 1, problem);
+    }
+    Ok(())
+}
+
 pub async fn validate_one_file(
     source_lang: &str,
     target_lang: &str,
-    source_file: &std::path::PathBuf,
-    translated_file: &std::path::PathBuf,
+    source_file: &std::path::Path,
+    translated_file: &std::path::Path,
+    validators: &Validators,
+    fix: bool,
+    writer: &mut postprocess::TargetWriter,
 ) -> Result<()> {
     use std::fs;
 
     let source = fs::read_to_string(source_file)?;
-    let translated = fs::read_to_string(translated_file)?;
+    let mut translated = fs::read_to_string(translated_file)?;
+
+    // 按原文修复可以机械修复的标记，并改写译文文件
+    if fix {
+        let (fixed, fixes) = postprocess::fix_markup(
+            validators.format.markup(),
+            source.trim_start_matches('\u{FEFF}'),
+            translated.trim_start_matches('\u{FEFF}'),
+        );
+        if !fixes.is_empty() {
+            writer.write(&fixed, translated_file, &source)?;
+            log::info!(
+                "Fixed {} entries in {}:",
+                fixes.len(),
+                translated_file.display()
+            );
+            for fix in &fixes {
+                log::info!("  - {}", fix);
+            }
+            translated = fs::read_to_string(translated_file)?;
+        }
+    }
 
     // 去除 BOM 头
     let source = source.trim_start_matches("\u{FEFF}");
@@ -317,9 +541,10 @@ pub async fn validate_one_file(
     let source = fix_yaml_content(&source)?;
     let translated = fix_yaml_content(&translated)?;
 
-    let validator = FormatValidator::new();
-    // 检查 key 的数量和名称是否一一对应
-    let issues = validator.validate(&source, &translated);
+    // 检查 key 是否一一对应、格式标记是否被破坏，转义、引号、跨行与不可见字符，
+    // 术语表规定的译法，禁止翻译的文本是否被原样保留，译文是否未翻译或不是目标语言，
+    // 数字是否一致，以及译文长度是否异常
+    let issues = validators.validate(&source, &translated);
     if issues.is_empty() {
         log::info!(
             "[x] Validation passed for file {}",
@@ -334,13 +559,3 @@ pub async fn validate_one_file(
 
     Ok(())
 }
-
-#[cfg(test)]
-mod tests {
-    // Keep existing test structure for now
-    #[test]
-    fn it_works() {
-        // Simple placeholder test
-        assert_eq!(2 + 2, 4);
-    }
-}
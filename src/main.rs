@@ -5,8 +5,14 @@
 use clap::{Parser, Subcommand};
 use ftail::Ftail;
 use log::{LevelFilter, Log};
+use paradox_mod_translator::commands::{
+    glossary_add, glossary_explain, glossary_export, glossary_harvest, glossary_import,
+    glossary_lint, glossary_list, glossary_remove, glossary_search, glossary_show, restore,
+    restore_list,
+};
 use paradox_mod_translator::config::{TranslationTask, load_openai_api_key};
 use paradox_mod_translator::error::{Result, TranslationError};
+use paradox_mod_translator::translate::{GlossaryFormat, HarvestOptions};
 use paradox_mod_translator::{translate_task, validate_translation};
 use std::path::{Path, PathBuf};
 
@@ -38,9 +44,168 @@ enum Commands {
         /// 任务配置文件路径
         #[arg(value_name = "TASK_FILE")]
         task_file: PathBuf,
+
+        /// 按原文修复可以机械修复的标记，并改写译文文件
+        #[arg(long, default_value_t = false)]
+        fix: bool,
+    },
+    /// 将译文文件恢复为备份中的版本，默认回滚最近一次运行写入的所有文件
+    Restore {
+        /// 任务配置文件路径
+        #[arg(value_name = "TASK_FILE")]
+        task_file: PathBuf,
+
+        /// 要回滚的运行编号（备份目录中子目录的名称），默认为最近一次运行
+        #[arg(value_name = "RUN")]
+        run: Option<String>,
+
+        /// 只恢复这个文件
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// 列出所有备份，不恢复
+        #[arg(long, default_value_t = false)]
+        list: bool,
     },
     /// 检查API密钥
     CheckApi,
+    /// 管理术语表（修改只作用于 glossary_custom 中的自定义术语表）
+    Glossary {
+        #[command(subcommand)]
+        action: GlossaryCommands,
+    },
+}
+
+/// 术语表子命令
+#[derive(Subcommand)]
+enum GlossaryCommands {
+    /// 列出术语表中的所有条目及其来源
+    List {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+    /// 搜索 key 或术语中包含关键字的条目
+    Search {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// 搜索关键字（不区分大小写）
+        #[arg(value_name = "QUERY")]
+        query: String,
+        /// 只搜索该语言的术语
+        #[arg(long)]
+        lang: Option<String>,
+    },
+    /// 添加或更新自定义术语表中的条目
+    Add {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// 条目的 key，默认为英文术语的小写形式
+        #[arg(long)]
+        key: Option<String>,
+        /// 术语，格式为 LANG=TERM，可多次指定
+        #[arg(short, long = "term", value_name = "LANG=TERM", required = true)]
+        terms: Vec<String>,
+    },
+    /// 从自定义术语表中删除条目
+    Remove {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// 条目的 key
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+    /// 显示单个条目，以及定义了该条目的所有术语表文件
+    Show {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// 条目的 key
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+    /// 按任务配置的术语表层级，解释条目来自哪一层以及覆盖了哪些定义
+    Explain {
+        /// 任务配置文件路径
+        #[arg(value_name = "TASK_FILE")]
+        task_file: PathBuf,
+        /// 条目的 key
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+    /// 从 CSV/TSV/TBX 文件导入条目到自定义术语表
+    Import {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// 要导入的文件
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 文件格式（csv、tsv、tbx），默认根据扩展名推断
+        #[arg(long, value_parser = parse_glossary_format)]
+        format: Option<GlossaryFormat>,
+        /// 导入前清空自定义术语表，而不是与已有条目合并
+        #[arg(long, default_value_t = false)]
+        replace: bool,
+    },
+    /// 将术语表（默认 + 自定义）导出为 CSV/TSV/TBX 文件
+    Export {
+        /// 术语表名称（不带 .json 扩展名）
+        #[arg(value_name = "NAME")]
+        name: String,
+        /// 输出文件
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 文件格式（csv、tsv、tbx），默认根据扩展名推断
+        #[arg(long, value_parser = parse_glossary_format)]
+        format: Option<GlossaryFormat>,
+        /// 只导出这些语言，可多次指定，默认导出所有语言
+        #[arg(long = "lang")]
+        langs: Vec<String>,
+    },
+    /// 检查术语表中不合法的条目，以及依次合并这些术语表时的冲突
+    Lint {
+        /// 术语表名称（不带 .json 扩展名），按给出的顺序合并，后者优先
+        #[arg(value_name = "NAME", required = true)]
+        names: Vec<String>,
+        /// 用于检查重复源术语的源语言
+        #[arg(long, default_value = "english")]
+        source: String,
+    },
+    /// 对齐游戏原版的本地化文件，采集名称类术语写入自定义术语表 glossary_custom/NAME.json
+    Harvest {
+        /// 游戏安装目录（包含 localisation/ 的目录）
+        #[arg(long, value_name = "PATH")]
+        game_dir: PathBuf,
+        /// 源语言
+        #[arg(long, default_value = "english")]
+        source: String,
+        /// 目标语言
+        #[arg(long, default_value = "simp_chinese")]
+        target: String,
+        /// 输出的术语表名称（不带 .json 扩展名）
+        #[arg(long, default_value = "stellaris")]
+        name: String,
+        /// 写入的术语表文件，默认为数据目录中的 glossary_custom/NAME.json
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// 候选术语的最大单词数
+        #[arg(long, default_value_t = 4)]
+        max_words: usize,
+        /// 候选术语在源文本中出现的最少次数
+        #[arg(long, default_value_t = 3)]
+        min_frequency: usize,
+        /// 采集结果覆盖术语表中已有的同名条目
+        #[arg(long, default_value_t = false)]
+        overwrite: bool,
+    },
+}
+
+fn parse_glossary_format(name: &str) -> std::result::Result<GlossaryFormat, String> {
+    GlossaryFormat::from_name(name).ok_or_else(|| format!("unknown glossary format '{}'", name))
 }
 
 /// 主函数
@@ -100,7 +265,7 @@ async fn main() -> Result<()> {
             log::info!("All translation tasks completed!");
             Ok(())
         }
-        Commands::Validate { task_file } => {
+        Commands::Validate { task_file, fix } => {
             log::info!("Validating translated task: {:?}", task_file);
 
             let (_client_settings, tasks) = TranslationTask::from_file(&task_file)?;
@@ -111,16 +276,31 @@ async fn main() -> Result<()> {
                 log::info!("Task {}:", i + 1);
                 log::info!("  - Source language: {}", task.source_lang);
                 log::info!("  - Target languages: {}", task.target_langs.join(", "));
-                log::info!("  - Glossaries: {}", task.glossaries.join(", "));
+                let glossaries: Vec<String> =
+                    task.glossaries.iter().map(|g| g.to_string()).collect();
+                log::info!("  - Glossaries: {}", glossaries.join(", "));
                 log::info!("  - Localisation directory: {:?}", task.localisation_dir);
+                log::info!("  - Localisation layout: {:?}", task.layout());
             }
 
             for task in tasks {
-                validate_translation(task).await?;
+                validate_translation(task, fix).await?;
             }
 
             Ok(())
         }
+        Commands::Restore {
+            task_file,
+            run,
+            file,
+            list,
+        } => {
+            if list {
+                restore_list(&task_file)
+            } else {
+                restore(&task_file, run.as_deref(), file.as_deref())
+            }
+        }
         Commands::CheckApi => {
             if paradox_mod_translator::config::has_api_key() {
                 log::info!("API key is configured");
@@ -148,5 +328,48 @@ async fn main() -> Result<()> {
 
             Ok(())
         }
+        Commands::Glossary { action } => match action {
+            GlossaryCommands::List { name } => glossary_list(&name),
+            GlossaryCommands::Search { name, query, lang } => {
+                glossary_search(&name, &query, lang.as_deref())
+            }
+            GlossaryCommands::Add { name, key, terms } => {
+                glossary_add(&name, key.as_deref(), &terms)
+            }
+            GlossaryCommands::Remove { name, key } => glossary_remove(&name, &key),
+            GlossaryCommands::Show { name, key } => glossary_show(&name, &key),
+            GlossaryCommands::Explain { task_file, key } => glossary_explain(&task_file, &key),
+            GlossaryCommands::Import {
+                name,
+                file,
+                format,
+                replace,
+            } => glossary_import(&name, &file, format, replace),
+            GlossaryCommands::Export {
+                name,
+                file,
+                format,
+                langs,
+            } => glossary_export(&name, &file, format, &langs),
+            GlossaryCommands::Lint { names, source } => glossary_lint(&names, &source),
+            GlossaryCommands::Harvest {
+                game_dir,
+                source,
+                target,
+                name,
+                output,
+                max_words,
+                min_frequency,
+                overwrite,
+            } => {
+                let options = HarvestOptions {
+                    source_lang: source,
+                    target_lang: target,
+                    max_words,
+                    min_frequency,
+                };
+                glossary_harvest(&game_dir, &name, output.as_deref(), &options, overwrite)
+            }
+        },
     }
 }
@@ -0,0 +1,293 @@
+//! 备份模块
+//!
+//! 覆盖译文文件前将原有的文件复制到备份目录，以免重新翻译时丢失人工修改。
+//! 每次运行在备份目录中建立一个以时间命名的子目录，其中的 `manifest.json` 记录每个备份对应的原路径；
+//! 运行中新建的文件也会记录，回滚时将其删除。
+
+use crate::error::{Result, TranslationError};
+use crate::postprocess::write_atomically;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// 清单文件名
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 一个被覆盖或新建的文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// 文件的原路径
+    pub path: PathBuf,
+    /// 备份文件相对本次运行目录的路径，运行前文件不存在时为 None
+    pub backup: Option<PathBuf>,
+}
+
+/// 一次运行的备份清单
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// 运行的编号，即备份目录中子目录的名称
+    pub id: String,
+    pub entries: Vec<BackupEntry>,
+}
+
+impl BackupManifest {
+    /// 清单中某文件的记录
+    pub fn entry(&self, path: &Path) -> Option<&BackupEntry> {
+        self.entries
+            .iter()
+            .find(|e| normalize(&e.path) == normalize(path))
+    }
+}
+
+/// 一次运行的备份，第一次备份文件时才创建运行目录
+pub struct BackupRun {
+    root: PathBuf,
+    manifest: BackupManifest,
+    saved: HashSet<PathBuf>,
+}
+
+impl BackupRun {
+    pub fn new(backup_dir: &Path) -> Self {
+        Self {
+            root: backup_dir.to_path_buf(),
+            manifest: BackupManifest::default(),
+            saved: HashSet::new(),
+        }
+    }
+
+    /// 本次运行的编号，尚未备份任何文件时为 None
+    pub fn id(&self) -> Option<&str> {
+        (!self.manifest.id.is_empty()).then_some(self.manifest.id.as_str())
+    }
+
+    /// 在覆盖或新建文件之前调用，备份文件原有的内容
+    ///
+    /// 同一次运行中只备份第一次写入前的内容
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        if !self.saved.insert(normalize(path)) {
+            return Ok(());
+        }
+        let dir = self.run_dir()?;
+        let backup = if path.exists() {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let backup = PathBuf::from(format!("{:04}_{}", self.manifest.entries.len() + 1, name));
+            fs::copy(path, dir.join(&backup))?;
+            Some(backup)
+        } else {
+            None
+        };
+        self.manifest.entries.push(BackupEntry {
+            path: path.to_path_buf(),
+            backup,
+        });
+        let manifest =
+            serde_json::to_string_pretty(&self.manifest).map_err(std::io::Error::from)?;
+        write_atomically(&dir.join(MANIFEST_FILE), manifest.as_bytes())
+    }
+
+    /// 本次运行的目录，第一次调用时以当前时间命名并创建
+    fn run_dir(&mut self) -> Result<PathBuf> {
+        if let Some(id) = self.id() {
+            return Ok(self.root.join(id));
+        }
+        fs::create_dir_all(&self.root)?;
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut id = timestamp.clone();
+        // 同一秒内的多次运行依次编号
+        for n in 2.. {
+            match fs::create_dir(self.root.join(&id)) {
+                Ok(()) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    id = format!("{}-{}", timestamp, n);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        log::info!(
+            "Backing up overwritten files to {}",
+            self.root.join(&id).display()
+        );
+        self.manifest.id = id;
+        Ok(self.root.join(&self.manifest.id))
+    }
+}
+
+/// 备份目录中的所有运行，按时间排序
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<BackupManifest>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut runs = Vec::new();
+    for entry in fs::read_dir(backup_dir)? {
+        let manifest_path = entry?.path().join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let content = fs::read_to_string(&manifest_path)?;
+        let manifest: BackupManifest = serde_json::from_str(&content)
+            .map_err(|e| std::io::Error::other(format!("{}: {}", manifest_path.display(), e)))?;
+        runs.push(manifest);
+    }
+    runs.sort_by(|a, b| run_order(&a.id).cmp(&run_order(&b.id)));
+    Ok(runs)
+}
+
+/// 将文件恢复为某次运行之前的内容，返回恢复的文件
+///
+/// 未指定运行时使用最近一次（指定了文件时为最近一次包含该文件的）运行；
+/// 未指定文件时恢复该运行写入的所有文件。恢复前当前的内容会备份到 `backup` 中
+pub fn restore_backup(
+    backup_dir: &Path,
+    run: Option<&str>,
+    file: Option<&Path>,
+    backup: &mut BackupRun,
+) -> Result<Vec<PathBuf>> {
+    let runs = list_backups(backup_dir)?;
+    let manifest = match run {
+        Some(id) => runs.iter().find(|m| m.id == id).ok_or_else(|| {
+            TranslationError::FileNotFound(format!(
+                "No backup run '{}' in {}",
+                id,
+                backup_dir.display()
+            ))
+        })?,
+        None => runs
+            .iter()
+            .rev()
+            .find(|m| file.is_none_or(|f| m.entry(f).is_some()))
+            .ok_or_else(|| {
+                TranslationError::FileNotFound(format!("No backups in {}", backup_dir.display()))
+            })?,
+    };
+    let entries: Vec<&BackupEntry> = match file {
+        Some(file) => vec![manifest.entry(file).ok_or_else(|| {
+            TranslationError::FileNotFound(format!(
+                "Backup run '{}' does not contain {}",
+                manifest.id,
+                file.display()
+            ))
+        })?],
+        None => manifest.entries.iter().collect(),
+    };
+
+    let run_dir = backup_dir.join(&manifest.id);
+    let mut restored = Vec::new();
+    for entry in entries {
+        backup.save(&entry.path)?;
+        match &entry.backup {
+            Some(saved) => {
+                if let Some(parent) = entry.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_atomically(&entry.path, &fs::read(run_dir.join(saved))?)?;
+            }
+            None => match fs::remove_file(&entry.path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+        }
+        restored.push(entry.path.clone());
+    }
+    Ok(restored)
+}
+
+/// 运行的先后顺序：编号为时间，同一秒内的运行带有 `-N` 序号
+pub(crate) fn run_order(id: &str) -> (&str, u32) {
+    match id.split_at_checked(15) {
+        Some((timestamp, n)) if !n.is_empty() => {
+            (timestamp, n.trim_start_matches('-').parse().unwrap_or(0))
+        }
+        _ => (id, 1),
+    }
+}
+
+/// 去掉路径中的 `.`，用于比较同一文件的不同写法
+pub(super) fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postprocess::write_translated_file;
+    use tempfile::TempDir;
+
+    /// 一次运行覆盖了已有的 a 并新建了 b，返回临时目录与运行编号
+    fn run() -> (TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let (backups, existing, created) = paths(&dir);
+        write_translated_file(" a: \"人工修改\"\n", &existing, true).unwrap();
+
+        let mut run = BackupRun::new(&backups);
+        for (path, content) in [(&existing, " a: \"甲\"\n"), (&created, " b: \"乙\"\n")] {
+            run.save(path).unwrap();
+            write_translated_file(content, path, true).unwrap();
+        }
+        run.save(&existing).unwrap();
+        let id = run.id().unwrap().to_string();
+        (dir, id)
+    }
+
+    fn paths(dir: &TempDir) -> (PathBuf, PathBuf, PathBuf) {
+        (
+            dir.path().join("backups"),
+            dir.path().join("loc/replace/a_l_simp_chinese.yml"),
+            dir.path().join("loc/replace/b_l_simp_chinese.yml"),
+        )
+    }
+
+    #[test]
+    fn test_backup_each_file_once_per_run() {
+        let (dir, _) = run();
+        let (backups, _, _) = paths(&dir);
+        let runs = list_backups(&backups).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].entries.len(), 2);
+        // 新建的文件没有备份内容，恢复时删除
+        assert_eq!(runs[0].entries[1].backup, None);
+    }
+
+    #[test]
+    fn test_restore_single_file() {
+        let (dir, _) = run();
+        let (backups, existing, created) = paths(&dir);
+        let mut undo = BackupRun::new(&backups);
+        let restored = restore_backup(&backups, None, Some(&existing), &mut undo).unwrap();
+        assert_eq!(restored, std::slice::from_ref(&existing));
+        assert!(fs::read_to_string(&existing).unwrap().contains("人工修改"));
+        assert!(created.exists());
+    }
+
+    #[test]
+    fn test_restore_whole_run() {
+        let (dir, id) = run();
+        let (backups, existing, created) = paths(&dir);
+        let mut undo = BackupRun::new(&backups);
+        restore_backup(&backups, Some(&id), None, &mut undo).unwrap();
+        assert!(fs::read_to_string(&existing).unwrap().contains("人工修改"));
+        assert!(!created.exists());
+        // 恢复本身也会备份，可以撤销
+        assert_eq!(list_backups(&backups).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_restore_unknown_run() {
+        let (dir, _) = run();
+        let (backups, _, _) = paths(&dir);
+        let mut undo = BackupRun::new(&backups);
+        assert!(restore_backup(&backups, Some("missing"), None, &mut undo).is_err());
+    }
+
+    #[test]
+    fn test_run_order_within_one_second() {
+        assert!(run_order("20261018-120000-10") > run_order("20261018-120000-9"));
+        assert!(run_order("20261018-120000-2") > run_order("20261018-120000"));
+        assert!(run_order("20261018-120001") > run_order("20261018-120000-10"));
+    }
+}
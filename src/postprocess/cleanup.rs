@@ -23,12 +23,11 @@ pub fn cleanup_files_by_extension(dir: &Path, extension: &str) -> Result<()> {
         let entry = entry?;
         let path = entry.path();
 
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext == extension {
-                    std::fs::remove_file(&path)?;
-                }
-            }
+        if path.is_file()
+            && let Some(ext) = path.extension()
+            && ext == extension
+        {
+            std::fs::remove_file(&path)?;
         }
     }
 
@@ -0,0 +1,272 @@
+//! 标记修复模块
+//!
+//! 大模型常常机械地破坏标记：把 `$energy$` 译成 `$能量$`、丢掉 `§!`、把 `£` 写成全角的 `￡`。
+//! 这类问题可以按原文的标记确定地修复：
+//! 先将全角的标记符号改回半角，再将译文中与原文对不上的标记按类别与出现顺序替换为原文的标记，
+//! 最后为译文中多出的未闭合颜色与文本格式在值末尾补上 `§!`、`#!`。
+//! 修复后的条目必须通过格式验证才会采用，否则保持原样并继续报告。
+
+use crate::preprocess::{parse_entries, parse_entry_line, value_range};
+use crate::translate::{FormatValidator, MarkupIssue, MarkupKind, MarkupSyntax, MarkupToken};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// 全角的标记符号及其对应的半角符号
+const LOOKALIKES: [(char, char); 4] = [('￡', '£'), ('＄', '$'), ('［', '['), ('］', ']')];
+
+/// 对一个条目的修复
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupFix {
+    pub key: String,
+    /// 修复前的值
+    pub before: String,
+    /// 修复后的值
+    pub after: String,
+}
+
+impl Display for MarkupFix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Key '{}': \"{}\" -> \"{}\"",
+            self.key, self.before, self.after
+        )
+    }
+}
+
+/// 按原文修复译文中被破坏的标记，返回修复后的内容与所做的修复
+///
+/// 只修改条目的值，其余行保持不变
+pub fn fix_markup(
+    markup: MarkupSyntax,
+    original: &str,
+    translated: &str,
+) -> (String, Vec<MarkupFix>) {
+    // 同名键以先出现的为准
+    let mut original_map: HashMap<String, String> = HashMap::new();
+    for entry in parse_entries(original) {
+        original_map.entry(entry.key).or_insert(entry.value);
+    }
+    let validator = FormatValidator::with_markup(markup);
+    let mut fixes = Vec::new();
+    let mut content = String::with_capacity(translated.len());
+    for line in translated.split_inclusive('\n') {
+        let fixed = parse_entry_line(line, 0).and_then(|entry| {
+            let original_value = original_map.get(&entry.key)?;
+            let range = value_range(line)?;
+            let value = &line[range.clone()];
+            let mut problems = Vec::new();
+            if validator.validate_entry(&entry.key, original_value, value, &mut problems) == 0 {
+                return None;
+            }
+            let after = fix_value(markup, original_value, value);
+            problems.clear();
+            if after == value
+                || validator.validate_entry(&entry.key, original_value, &after, &mut problems) > 0
+            {
+                return None;
+            }
+            let fixed_line = format!("{}{}{}", &line[..range.start], after, &line[range.end..]);
+            fixes.push(MarkupFix {
+                key: entry.key,
+                before: value.to_string(),
+                after,
+            });
+            Some(fixed_line)
+        });
+        content.push_str(fixed.as_deref().unwrap_or(line));
+    }
+    (content, fixes)
+}
+
+/// 按原文的标记修复单个值
+fn fix_value(markup: MarkupSyntax, original: &str, translated: &str) -> String {
+    let normalized: String = translated
+        .chars()
+        .map(|c| {
+            LOOKALIKES
+                .iter()
+                .find(|(lookalike, _)| *lookalike == c)
+                .map_or(c, |(_, ascii)| *ascii)
+        })
+        .collect();
+
+    let original_tokens = markup.tokenize(original);
+    let translated_tokens = markup.tokenize(&normalized);
+    // 与原文相同的标记保持不变，其余标记按类别与出现顺序与原文中未对上的标记配对
+    let mut unmatched: Vec<&MarkupToken> = original_tokens
+        .iter()
+        .filter(|t| token_kind(t).is_some())
+        .collect();
+    let mut changed: Vec<usize> = Vec::new();
+    for (i, token) in translated_tokens.iter().enumerate() {
+        if token_kind(token).is_none() {
+            continue;
+        }
+        match unmatched.iter().position(|t| t.as_str() == token.as_str()) {
+            Some(j) => {
+                unmatched.remove(j);
+            }
+            None => changed.push(i),
+        }
+    }
+    let mut kinds: Vec<MarkupKind> = changed
+        .iter()
+        .filter_map(|i| token_kind(&translated_tokens[*i]))
+        .collect();
+    kinds.sort();
+    kinds.dedup();
+    let mut replacements: HashMap<usize, String> = HashMap::new();
+    for kind in kinds {
+        let targets: Vec<usize> = changed
+            .iter()
+            .copied()
+            .filter(|i| token_kind(&translated_tokens[*i]) == Some(kind))
+            .collect();
+        let sources: Vec<&&MarkupToken> = unmatched
+            .iter()
+            .filter(|t| token_kind(t) == Some(kind))
+            .collect();
+        // 数量不同时无法确定对应关系
+        if targets.len() != sources.len() {
+            continue;
+        }
+        for (i, source) in targets.into_iter().zip(sources) {
+            let replacement = match (source, &translated_tokens[i]) {
+                // 概念命令只替换概念的 key，保留译文中的说明文本
+                (
+                    MarkupToken::Concept { raw, text, .. },
+                    MarkupToken::Concept { text: own, .. },
+                ) => {
+                    format!("{}{}]", &raw[..raw.len() - 1 - text.len()], own)
+                }
+                (source, _) => source.as_str().to_string(),
+            };
+            replacements.insert(i, replacement);
+        }
+    }
+    let mut fixed = String::with_capacity(normalized.len());
+    for (i, token) in translated_tokens.iter().enumerate() {
+        match replacements.get(&i) {
+            Some(replacement) => fixed.push_str(replacement),
+            None => fixed.push_str(token.as_str()),
+        }
+    }
+
+    // 补上丢失的 `§!` 与 `#!`
+    let original_issues = markup.issues(original);
+    let new_issues = |issue: fn(&MarkupIssue) -> bool, text: &str| {
+        let count = markup.issues(text).iter().filter(|i| issue(i)).count();
+        count.saturating_sub(original_issues.iter().filter(|i| issue(i)).count())
+    };
+    let count = |end: fn(&MarkupToken) -> bool, text: &str| {
+        markup.tokenize(text).iter().filter(|t| end(t)).count()
+    };
+    let unclosed_colors = new_issues(|i| matches!(i, MarkupIssue::UnclosedColor(_)), &fixed);
+    let color_ends = count(|t| matches!(t, MarkupToken::ColorEnd(_)), original);
+    if unclosed_colors > 0
+        && color_ends == count(|t| matches!(t, MarkupToken::ColorEnd(_)), &fixed) + unclosed_colors
+    {
+        fixed.push_str(&"§!".repeat(unclosed_colors));
+    }
+    let unclosed_formats = new_issues(|i| matches!(i, MarkupIssue::UnclosedFormat(_)), &fixed);
+    let format_ends = count(|t| matches!(t, MarkupToken::FormatEnd(_)), original);
+    if unclosed_formats > 0
+        && format_ends
+            == count(|t| matches!(t, MarkupToken::FormatEnd(_)), &fixed) + unclosed_formats
+    {
+        fixed.push_str(&"#!".repeat(unclosed_formats));
+    }
+    fixed
+}
+
+/// 可以按原文替换内容的标记类别，结束标记与普通文本返回 None
+fn token_kind(token: &MarkupToken) -> Option<MarkupKind> {
+    match token {
+        MarkupToken::ColorStart(_) => Some(MarkupKind::Color),
+        MarkupToken::Variable(_) => Some(MarkupKind::Variable),
+        MarkupToken::Icon(_) => Some(MarkupKind::Icon),
+        MarkupToken::Command(_) => Some(MarkupKind::Command),
+        MarkupToken::Concept { .. } => Some(MarkupKind::Concept),
+        MarkupToken::FormatStart(_) => Some(MarkupKind::Format),
+        MarkupToken::TextIcon(_) => Some(MarkupKind::TextIcon),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 修复单个条目，返回修复后的值与修复次数
+    fn fix(original: &str, translated: &str) -> (String, usize) {
+        let (content, fixes) = fix_markup(
+            MarkupSyntax::STELLARIS,
+            &format!("l_english:\n a:0 \"{}\"\n", original),
+            &format!("l_simp_chinese:\n a:0 \"{}\"\n", translated),
+        );
+        let value = parse_entries(&content).remove(0).value;
+        (value, fixes.len())
+    }
+
+    #[test]
+    fn test_restores_translated_variables() {
+        assert_eq!(
+            fix("Gain $energy$ and $minerals$", "获得$能量$和$矿物$"),
+            ("获得$energy$和$minerals$".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_restores_full_width_icon_markers() {
+        assert_eq!(
+            fix("§YWarning§! costs £energy£", "§Y警告§!花费￡energy￡"),
+            ("§Y警告§!花费£energy£".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_closes_unclosed_colour() {
+        assert_eq!(
+            fix("§YImportant§! news", "§Y重要新闻"),
+            ("§Y重要新闻§!".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_restores_misspelled_references() {
+        assert_eq!(
+            fix(
+                "['pop_growth', Growth] for [Root.GetName]",
+                "为[Root.GetNombre]的['pop_growthh', 增长]"
+            ),
+            ("为[Root.GetName]的['pop_growth', 增长]".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn test_restores_remaining_variable_when_others_kept() {
+        assert_eq!(fix("$A$ and $B$", "$B$与$乙$"), ("$B$与$A$".to_string(), 1));
+    }
+
+    #[test]
+    fn test_skips_ambiguous_variables() {
+        assert_eq!(
+            fix("Uses $A$", "使用$甲$和$乙$"),
+            ("使用$甲$和$乙$".to_string(), 0)
+        );
+    }
+
+    #[test]
+    fn test_fix_description() {
+        let (_, fixes) = fix_markup(
+            MarkupSyntax::STELLARIS,
+            "l_english:\n c:0 \"§YImportant§! news\"\n",
+            "l_simp_chinese:\n c:0 \"§Y重要新闻\"\n",
+        );
+        assert_eq!(
+            fixes[0].to_string(),
+            "Key 'c': \"§Y重要新闻\" -> \"§Y重要新闻§!\""
+        );
+    }
+}
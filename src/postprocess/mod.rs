@@ -1,11 +1,19 @@
 //! 后处理模块
 //!
-//! 负责合并翻译后的切片，写入目标目录，并清理临时文件。
+//! 负责合并翻译后的切片，整理为游戏可以解析的格式后写入目标目录，备份被覆盖的文件，保留人工修改的条目，并清理临时文件。
 
+mod backup;
 mod cleanup;
+mod markup_fixer;
 mod merger;
+mod preserve;
+mod sanitizer;
 mod writer;
 
+pub use backup::*;
 pub use cleanup::*;
+pub use markup_fixer::*;
 pub use merger::*;
+pub use preserve::*;
+pub use sanitizer::*;
 pub use writer::*;
@@ -0,0 +1,427 @@
+//! 人工修改保留模块
+//!
+//! 校对者常常直接修改译文文件中的条目，重新翻译时不应覆盖这些修改。
+//! pmt 写入译文时在状态文件中记录每个条目的值的哈希，重新翻译前与已有的译文对比：
+//! 值与上次写入时不同的条目视为人工修改；上一行为 `# pmt:lock` 注释的条目视为锁定。
+//! 这两类条目原样保留，只翻译其余的条目。
+//! 状态文件同时记录写入时原文各条目的哈希，保留的条目在原文中被删除或修改时给出提示。
+//! 状态文件随译文一起备份，恢复备份时一并恢复，否则恢复的条目会被误认为人工修改。
+
+use super::backup::normalize;
+use crate::error::Result;
+use crate::postprocess::{BackupRun, list_backups, write_atomically};
+use crate::preprocess::{parse_entries, parse_entry_line};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// 锁定注释，写在条目的上一行
+pub const LOCK_COMMENT: &str = "# pmt:lock";
+
+/// 条目的值的哈希（FNV-1a）
+pub fn entry_hash(value: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in value.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// pmt 上次写入每个译文文件时各条目的哈希
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WrittenHashes {
+    /// 译文文件路径 → 键名 → 哈希
+    files: BTreeMap<String, BTreeMap<String, String>>,
+    /// 译文文件路径 → 键名 → 写入时原文的哈希
+    #[serde(default)]
+    sources: BTreeMap<String, BTreeMap<String, String>>,
+    /// 状态文件所在的目录，其中的译文文件以相对路径记录
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl WrittenHashes {
+    /// 读取状态文件，文件不存在时返回空记录
+    pub fn load(path: &Path) -> Result<Self> {
+        let root = path.parent().map(normalize).unwrap_or_default();
+        if !path.exists() {
+            return Ok(Self {
+                root,
+                ..Self::default()
+            });
+        }
+        let content = std::fs::read_to_string(path)?;
+        let hashes: Self = serde_json::from_str(&content)
+            .map_err(|e| std::io::Error::other(format!("{}: {}", path.display(), e)))?;
+        Ok(Self { root, ..hashes })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        write_atomically(path, content.as_bytes())
+    }
+
+    /// 某译文文件中各条目的哈希
+    pub fn get(&self, file: &Path) -> Option<&BTreeMap<String, String>> {
+        self.files.get(&self.file_key(file))
+    }
+
+    /// 上次写入某译文文件时原文各条目的哈希
+    pub fn source_hashes(&self, file: &Path) -> Option<&BTreeMap<String, String>> {
+        self.sources.get(&self.file_key(file))
+    }
+
+    /// 记录写入译文时的原文
+    pub fn record_source(&mut self, file: &Path, source: &str) {
+        let hashes = parse_entries(source)
+            .into_iter()
+            .map(|entry| (entry.key, entry_hash(&entry.value)))
+            .collect();
+        self.sources.insert(self.file_key(file), hashes);
+    }
+
+    /// 记录写入后的文件内容
+    ///
+    /// `previous` 为写入前的文件内容，其中的人工修改即使被原样写回，也保留原来的哈希，
+    /// 以便下次仍然识别为人工修改
+    pub fn record(&mut self, file: &Path, previous: Option<&str>, written: &str) {
+        let old = self.files.remove(&self.file_key(file)).unwrap_or_default();
+        let edited: HashSet<String> = previous
+            .map(|content| edited_keys(content, &old))
+            .unwrap_or_default();
+        let mut hashes = BTreeMap::new();
+        for entry in parse_entries(written) {
+            let hash = match old.get(&entry.key) {
+                Some(hash) if edited.contains(&entry.key) => hash.clone(),
+                _ => entry_hash(&entry.value),
+            };
+            hashes.entry(entry.key).or_insert(hash);
+        }
+        self.files.insert(self.file_key(file), hashes);
+    }
+
+    /// 将这些译文文件的记录改为 `previous` 中的记录，`previous` 中没有记录的文件删除记录
+    pub fn restore_files(&mut self, previous: &Self, files: &[PathBuf]) {
+        for file in files {
+            let key = self.file_key(file);
+            for (current, previous) in [
+                (&mut self.files, &previous.files),
+                (&mut self.sources, &previous.sources),
+            ] {
+                match previous.get(&key) {
+                    Some(hashes) => current.insert(key.clone(), hashes.clone()),
+                    None => current.remove(&key),
+                };
+            }
+        }
+    }
+
+    /// 状态文件中译文文件的键：去掉路径中的 `.`，位于状态文件所在目录中时使用相对路径
+    fn file_key(&self, file: &Path) -> String {
+        let file = normalize(file);
+        file.strip_prefix(&self.root)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+/// 恢复备份后，将状态文件中恢复的文件的记录改回该次运行之前的状态
+///
+/// 回滚整次运行时状态文件已随译文一起恢复；只恢复部分文件时，从备份的状态文件中取回这些文件的记录。
+/// 备份中没有状态文件时（状态文件在该次运行中创建，或备份由旧版本生成），删除这些文件的记录
+pub fn restore_written_hashes(
+    state_file: &Path,
+    backup_dir: &Path,
+    run: &str,
+    restored: &[PathBuf],
+    backup: &mut BackupRun,
+) -> Result<()> {
+    let state = normalize(state_file);
+    if restored.iter().any(|path| normalize(path) == state) {
+        return Ok(());
+    }
+    let saved = list_backups(backup_dir)?
+        .into_iter()
+        .find(|m| m.id == run)
+        .and_then(|m| m.entry(state_file).and_then(|e| e.backup.clone()));
+    let previous = match saved {
+        Some(saved) => WrittenHashes::load(&backup_dir.join(run).join(saved))?,
+        None => WrittenHashes::default(),
+    };
+    let mut hashes = WrittenHashes::load(state_file)?;
+    hashes.restore_files(&previous, restored);
+    backup.save(state_file)?;
+    hashes.save(state_file)
+}
+
+/// 值与记录的哈希不同的条目
+fn edited_keys(content: &str, hashes: &BTreeMap<String, String>) -> HashSet<String> {
+    parse_entries(content)
+        .into_iter()
+        .filter(|e| {
+            hashes
+                .get(&e.key)
+                .is_some_and(|h| *h != entry_hash(&e.value))
+        })
+        .map(|e| e.key)
+        .collect()
+}
+
+/// 已有译文中需要保留的条目
+#[derive(Debug, Default)]
+pub struct KeptEntries {
+    /// 键名与原样保留的行（锁定的条目包括锁定注释），按译文中的顺序排列
+    entries: Vec<(String, String)>,
+    /// 人工修改的条目数
+    pub edited: usize,
+    /// 锁定的条目数
+    pub locked: usize,
+}
+
+impl KeptEntries {
+    /// 找出已有译文中人工修改或锁定的条目，`hashes` 为上次写入时记录的哈希
+    pub fn find(content: &str, hashes: Option<&BTreeMap<String, String>>) -> Self {
+        let edited = hashes
+            .map(|hashes| edited_keys(content, hashes))
+            .unwrap_or_default();
+        let mut kept = Self::default();
+        let mut lock: Option<&str> = None;
+        for line in content.trim_start_matches('\u{FEFF}').split_inclusive('\n') {
+            let trimmed = line.trim();
+            if trimmed == LOCK_COMMENT {
+                lock = Some(line);
+                continue;
+            }
+            if let Some(entry) = parse_entry_line(line, 0)
+                && !kept.contains(&entry.key)
+            {
+                let mut text = String::new();
+                if let Some(lock) = lock {
+                    text.push_str(lock);
+                    kept.locked += 1;
+                } else if edited.contains(&entry.key) {
+                    kept.edited += 1;
+                }
+                if lock.is_some() || edited.contains(&entry.key) {
+                    text.push_str(line);
+                    if !text.ends_with('\n') {
+                        text.push('\n');
+                    }
+                    kept.entries.push((entry.key, text));
+                }
+            }
+            if !trimmed.is_empty() {
+                lock = None;
+            }
+        }
+        kept
+    }
+
+    /// 保留的条目数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.iter().any(|(k, _)| k == key)
+    }
+
+    /// 源文本中已经没有的保留条目
+    pub fn removed_from(&self, source: &str) -> Vec<&str> {
+        let keys: HashSet<String> = parse_entries(source).into_iter().map(|e| e.key).collect();
+        self.entries
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .filter(|key| !keys.contains(*key))
+            .collect()
+    }
+
+    /// 原文与上次写入时不同的保留条目，`source_hashes` 为上次写入时记录的原文哈希
+    pub fn changed_in(
+        &self,
+        source: &str,
+        source_hashes: Option<&BTreeMap<String, String>>,
+    ) -> Vec<&str> {
+        let Some(source_hashes) = source_hashes else {
+            return Vec::new();
+        };
+        let changed: HashSet<String> = parse_entries(source)
+            .into_iter()
+            .filter(|e| {
+                source_hashes
+                    .get(&e.key)
+                    .is_some_and(|h| *h != entry_hash(&e.value))
+            })
+            .map(|e| e.key)
+            .collect();
+        self.entries
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .filter(|key| changed.contains(*key))
+            .collect()
+    }
+
+    /// 从源文本中去掉需要保留的条目，这些条目不再翻译
+    pub fn remove_from(&self, source: &str) -> String {
+        source
+            .split_inclusive('\n')
+            .filter(|line| parse_entry_line(line, 0).is_none_or(|e| !self.contains(&e.key)))
+            .collect()
+    }
+
+    /// 将保留的条目按源文本中的顺序插回译文，源文本中已经没有的条目放在最后
+    pub fn merge_into(&self, translated: &str, source: &str) -> String {
+        if self.entries.is_empty() {
+            return translated.to_string();
+        }
+        let mut position: HashMap<String, usize> = HashMap::new();
+        for entry in parse_entries(source) {
+            let next = position.len();
+            position.entry(entry.key).or_insert(next);
+        }
+        let mut pending: Vec<&(String, String)> = self.entries.iter().collect();
+        pending.sort_by_key(|(key, _)| position.get(key).copied().unwrap_or(usize::MAX));
+        let mut pending = pending.into_iter().peekable();
+
+        let mut merged = String::with_capacity(translated.len());
+        for line in translated.split_inclusive('\n') {
+            if let Some(current) = parse_entry_line(line, 0).and_then(|e| position.get(&e.key)) {
+                while let Some((_, text)) =
+                    pending.next_if(|(key, _)| position.get(key).is_some_and(|p| p < current))
+                {
+                    merged.push_str(text);
+                }
+            }
+            merged.push_str(line);
+        }
+        for (_, text) in pending {
+            if !merged.is_empty() && !merged.ends_with('\n') {
+                merged.push('\n');
+            }
+            merged.push_str(text);
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "loc/simp_chinese/replace/a_l_simp_chinese.yml";
+    const WRITTEN: &str = "l_simp_chinese:\n a:0 \"甲\"\n b:0 \"乙\"\n c:0 \"丙\"\n d:0 \"丁\"\n";
+    /// 校对者修改了 b，锁定了 c
+    const PROOFREAD: &str = "l_simp_chinese:\n a:0 \"甲\"\n b:0 \"乙（校对）\"\n # pmt:lock\n c:0 \"丙\"\n d:0 \"丁\"\n";
+    const SOURCE: &str = "a:0 \"A\"\nb:0 \"B\"\nc:0 \"C\"\nd:0 \"D\"\n";
+
+    fn written() -> WrittenHashes {
+        let mut hashes = WrittenHashes::default();
+        hashes.record(Path::new(PATH), None, WRITTEN);
+        hashes
+    }
+
+    fn kept() -> KeptEntries {
+        KeptEntries::find(PROOFREAD, written().get(Path::new(PATH)))
+    }
+
+    #[test]
+    fn test_keep_edited_and_locked_entries() {
+        let kept = kept();
+        assert_eq!((kept.len(), kept.edited, kept.locked), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_unchanged_entries_are_not_kept() {
+        let kept = KeptEntries::find(WRITTEN, written().get(Path::new(PATH)));
+        assert!(kept.is_empty());
+    }
+
+    #[test]
+    fn test_state_key_ignores_dot_prefix() {
+        let mut hashes = WrittenHashes::default();
+        hashes.record(Path::new("./loc/a_l_simp_chinese.yml"), None, WRITTEN);
+        assert!(hashes.get(Path::new("loc/a_l_simp_chinese.yml")).is_some());
+    }
+
+    #[test]
+    fn test_kept_entries_are_not_translated() {
+        assert_eq!(kept().remove_from(SOURCE), "a:0 \"A\"\nd:0 \"D\"\n");
+    }
+
+    #[test]
+    fn test_merge_kept_entries_in_source_order() {
+        let translated = "l_simp_chinese:\n  a:0 \"新甲\"\n  d:0 \"新丁\"";
+        assert_eq!(
+            kept().merge_into(translated, SOURCE),
+            "l_simp_chinese:\n  a:0 \"新甲\"\n b:0 \"乙（校对）\"\n # pmt:lock\n c:0 \"丙\"\n  d:0 \"新丁\""
+        );
+    }
+
+    #[test]
+    fn test_edits_survive_rewrite() {
+        let path = Path::new(PATH);
+        let mut hashes = written();
+        let merged = kept().merge_into("l_simp_chinese:\n a:0 \"新甲\"\n d:0 \"新丁\"", SOURCE);
+        hashes.record(path, Some(PROOFREAD), &merged);
+        let kept = KeptEntries::find(&merged, hashes.get(path));
+        assert_eq!((kept.edited, kept.locked), (1, 1));
+    }
+
+    #[test]
+    fn test_report_kept_entries_removed_from_source() {
+        let source = "a:0 \"A\"\nc:0 \"C\"\n";
+        assert_eq!(kept().removed_from(source), ["b"]);
+    }
+
+    #[test]
+    fn test_report_kept_entries_with_changed_source() {
+        let path = Path::new(PATH);
+        let mut hashes = written();
+        hashes.record_source(path, SOURCE);
+        let source = "a:0 \"A\"\nb:0 \"B, revised\"\nc:0 \"C\"\nd:0 \"D, revised\"\n";
+        assert_eq!(kept().changed_in(source, hashes.source_hashes(path)), ["b"]);
+    }
+
+    /// 两次运行写入同一个译文文件后恢复第二次运行，再次翻译时恢复的条目不应视为人工修改
+    fn assert_restored_entries_retranslated(file_only: bool) {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = dir.path().join("backups");
+        let state = dir.path().join(".pmt_state.json");
+        let target = dir.path().join("loc/a_l_simp_chinese.yml");
+        for translated in [" a:0 \"甲\"\n", " a:0 \"新甲\"\n"] {
+            let mut writer = crate::postprocess::TargetWriter::new(&backups, &state).unwrap();
+            writer.write(translated, &target, " a:0 \"A\"\n").unwrap();
+        }
+
+        let run = list_backups(&backups).unwrap().pop().unwrap().id;
+        let mut undo = BackupRun::new(&backups);
+        let file = file_only.then_some(target.as_path());
+        let restored =
+            crate::postprocess::restore_backup(&backups, Some(&run), file, &mut undo).unwrap();
+        restore_written_hashes(&state, &backups, &run, &restored, &mut undo).unwrap();
+
+        let content = std::fs::read_to_string(&target).unwrap();
+        assert!(content.contains("\"甲\""));
+        let hashes = WrittenHashes::load(&state).unwrap();
+        assert!(KeptEntries::find(&content, hashes.get(&target)).is_empty());
+    }
+
+    #[test]
+    fn test_restore_run_restores_state() {
+        assert_restored_entries_retranslated(false);
+    }
+
+    #[test]
+    fn test_restore_file_restores_its_state() {
+        assert_restored_entries_retranslated(true);
+    }
+}
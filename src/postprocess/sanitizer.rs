@@ -0,0 +1,216 @@
+//! 译文整理模块
+//!
+//! 写入前将译文整理为游戏可以解析的形式：跨行的值合并为一行并使用 `\n` 转义，
+//! 值内未转义的双引号加上转义，删除不可见的控制字符，无法识别的行改为注释。
+//! 值的结尾引号按 [`closing_quote`] 查找，行尾注释中的引号不属于值；缺少结尾引号的值不会吞并后面的条目。
+
+use crate::preprocess::{closing_quote, parse_entry_line};
+
+/// 是否为游戏无法显示的不可见字符（制表符之外的控制字符以及 BOM）
+pub fn is_non_printable(c: char) -> bool {
+    (c.is_control() && c != '\t' && c != '\n') || c == '\u{FEFF}'
+}
+
+/// 值中未转义的双引号的字节位置
+pub fn unescaped_quotes(value: &str) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '"' if !escaped => positions.push(i),
+            _ => escaped = false,
+        }
+    }
+    positions
+}
+
+/// 值中 `\n` 转义的个数
+pub fn count_newline_escapes(value: &str) -> usize {
+    let mut count = 0;
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            if c == 'n' {
+                count += 1;
+            }
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        }
+    }
+    count
+}
+
+/// 整理本地化文件内容，保证每个条目都是 `key:0 "value"` 形式的单行
+pub fn sanitize_localisation(content: &str) -> String {
+    let content = content.trim_start_matches('\u{FEFF}');
+    let mut lines: Vec<String> = Vec::new();
+    // 尚未找到结尾引号的条目：(行首到左引号的部分, 已读取的值)
+    let mut pending: Option<(String, String)> = None;
+
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some((prefix, mut value)) = pending.take() {
+            if opening_quote(line).is_some() {
+                // 下一个条目开始了，未闭合的值到此为止
+                lines.push(format_entry(&prefix, &value));
+            } else {
+                value.push('\n');
+                match closing_quote(line) {
+                    Some(end) => {
+                        value.push_str(&line[..end]);
+                        lines.push(format_entry(&prefix, &value));
+                    }
+                    None => {
+                        value.push_str(line);
+                        pending = Some((prefix, value));
+                    }
+                }
+                continue;
+            }
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            lines.push(String::new());
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            lines.push(line.trim_end().to_string());
+            continue;
+        }
+        let Some(entry) = parse_entry_line(line, 0) else {
+            if trimmed.ends_with(':') && !trimmed.contains(char::is_whitespace) {
+                // 语言头
+                lines.push(line.trim_end().to_string());
+            } else {
+                log::warn!("Commented out unrecognised line: {}", trimmed);
+                lines.push(format!("# {}", trimmed));
+            }
+            continue;
+        };
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let version = entry.version.map(|v| v.to_string()).unwrap_or_default();
+        let prefix = format!("{}{}:{}", indent, entry.key, version);
+        match opening_quote(line) {
+            // 只有左引号，值延续到后面的行
+            Some(quoted) if closing_quote(quoted).is_none() => {
+                pending = Some((prefix, quoted.to_string()));
+            }
+            _ => lines.push(format_entry(&prefix, &entry.value)),
+        }
+    }
+    if let Some((prefix, value)) = pending {
+        lines.push(format_entry(&prefix, &value));
+    }
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    result
+}
+
+/// 形如 `key:0 "…` 的条目行中左引号之后的部分，其他行返回 None
+fn opening_quote(line: &str) -> Option<&str> {
+    let (key, rest) = line.trim().split_once(':')?;
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    rest.trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start()
+        .strip_prefix('"')
+}
+
+/// 生成单行条目：值内的换行改为 `\n` 转义，未转义的双引号加上转义，删除不可见字符
+fn format_entry(prefix: &str, value: &str) -> String {
+    let quotes = unescaped_quotes(value);
+    let mut escaped = String::with_capacity(value.len());
+    for (i, c) in value.char_indices() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '"' if quotes.contains(&i) => escaped.push_str("\\\""),
+            c if is_non_printable(c) => {}
+            c => escaped.push(c),
+        }
+    }
+    format!("{} \"{}\"", prefix, escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_escapes_inner_quotes() {
+        assert_eq!(
+            sanitize_localisation("  a:0 \"他说\"你好\"\"\n"),
+            "  a:0 \"他说\\\"你好\\\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_removes_control_characters_and_trailing_spaces() {
+        assert_eq!(
+            sanitize_localisation("  a:0 \"你好\u{7}\"   \n"),
+            "  a:0 \"你好\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_joins_multiline_values() {
+        assert_eq!(
+            sanitize_localisation("  b: \"第一行\n第二行\"\n"),
+            "  b: \"第一行\\n第二行\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_quotes_bare_values() {
+        assert_eq!(
+            sanitize_localisation("  c:1 未加引号\n"),
+            "  c:1 \"未加引号\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_comments_out_stray_text() {
+        assert_eq!(
+            sanitize_localisation("l_simp_chinese:\n # 注释\n以下是译文：\n"),
+            "l_simp_chinese:\n # 注释\n# 以下是译文：\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_keeps_escaped_values() {
+        let content = "  d:0 \"已转义\\\"引号\\\"\\n\"\n";
+        assert_eq!(sanitize_localisation(content), content);
+    }
+
+    #[test]
+    fn test_sanitize_keeps_comment_quotes_out_of_value() {
+        assert_eq!(
+            sanitize_localisation(" key:0 \"text\" # \"note\"\n"),
+            " key:0 \"text\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_does_not_merge_entries() {
+        let content = " a:0 \"unterminated\n b:0 \"next\"\n";
+        assert_eq!(
+            sanitize_localisation(content),
+            " a:0 \"unterminated\"\n b:0 \"next\"\n"
+        );
+    }
+
+    #[test]
+    fn test_count_newline_escapes() {
+        assert_eq!(count_newline_escapes(r"a\nb\\n\n"), 2);
+    }
+
+    #[test]
+    fn test_unescaped_quotes() {
+        assert_eq!(unescaped_quotes(r#"a"b\"c"#), [1]);
+    }
+}
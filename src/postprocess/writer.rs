@@ -1,30 +1,98 @@
 //! 写入模块
 //!
 //! 将翻译后的内容写入目标目录。
+//! 先写入同目录下的临时文件再重命名，写入中途出错时不会留下不完整的文件。
+//! 翻译任务通过 [`TargetWriter`] 写入译文，覆盖前备份原有的文件，并记录写入的条目以识别之后的人工修改。
 
 use crate::error::Result;
+use crate::postprocess::{BackupRun, WrittenHashes, sanitize_localisation};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
 /// 写入翻译后的文件
 /// 因为 Rust str 本身编码为 UTF-8，所以只需要提前写入 BOM 头即可
+///
+/// 写入前会整理内容（见 [`sanitize_localisation`]），保证游戏可以解析
 pub fn write_translated_file(content: &str, output_path: &Path, create_dirs: bool) -> Result<()> {
-    if create_dirs {
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+    let content = sanitize_localisation(content);
+    if create_dirs && let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut bytes = Vec::with_capacity(content.len() + 3);
+    bytes.extend_from_slice("\u{FEFF}".as_bytes());
+    bytes.extend_from_slice(content.as_bytes());
+    write_atomically(output_path, &bytes)
+}
+
+/// 原子地写入文件：先写入同目录下的临时文件，再重命名为目标文件
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.tmp", name));
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// 译文文件的写入器：备份、原子写入并记录写入的条目
+pub struct TargetWriter {
+    backup: BackupRun,
+    hashes: WrittenHashes,
+    state_file: PathBuf,
+}
+
+impl TargetWriter {
+    /// `backup_dir` 为备份目录，`state_file` 为记录写入条目的状态文件
+    pub fn new(backup_dir: &Path, state_file: &Path) -> Result<Self> {
+        Ok(Self {
+            backup: BackupRun::new(backup_dir),
+            hashes: WrittenHashes::load(state_file)?,
+            state_file: state_file.to_path_buf(),
+        })
+    }
+
+    /// 上次写入该文件时各条目的哈希
+    pub fn hashes(&self, path: &Path) -> Option<&BTreeMap<String, String>> {
+        self.hashes.get(path)
+    }
+
+    /// 上次写入该文件时原文各条目的哈希
+    pub fn source_hashes(&self, path: &Path) -> Option<&BTreeMap<String, String>> {
+        self.hashes.source_hashes(path)
+    }
+
+    /// 本次运行的备份编号，没有备份任何文件时为 None
+    pub fn backup_id(&self) -> Option<&str> {
+        self.backup.id()
     }
-    let mut file = fs::OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(output_path)?;
-    if !content.starts_with("\u{FEFF}") {
-        file.write("\u{FEFF}".as_bytes())?;
+
+    /// 备份原有的文件后写入译文，并记录写入的条目与对应的原文
+    pub fn write(&mut self, content: &str, path: &Path, source: &str) -> Result<()> {
+        let previous = match fs::read_to_string(path) {
+            Ok(previous) => Some(previous),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        self.backup.save(path)?;
+        write_translated_file(content, path, true)?;
+        let written = fs::read_to_string(path)?;
+        self.hashes.record(path, previous.as_deref(), &written);
+        self.hashes.record_source(path, source);
+        // 状态文件随译文一起备份，恢复备份时一并恢复
+        self.backup.save(&self.state_file)?;
+        self.hashes.save(&self.state_file)
     }
-    file.write(content.as_bytes())?;
-    Ok(())
 }
 
 /// 批量写入翻译文件
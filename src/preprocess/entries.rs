@@ -0,0 +1,218 @@
+//! 本地化条目解析模块
+//!
+//! 从本地化文件内容中逐行解析出 `key:0 "value"` 形式的条目，容忍缺失的追踪号、
+//! 缺失的结尾引号和行尾注释。
+
+use crate::error::Result;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// 本地化条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalisationEntry {
+    /// 键名
+    pub key: String,
+    /// 冒号后的追踪号（可选）
+    pub version: Option<u32>,
+    /// 引号内的文本（不含引号，保留原始转义）
+    pub value: String,
+    /// 所在行号（从 1 开始）
+    pub line: usize,
+}
+
+/// 解析本地化文件内容中的所有条目
+///
+/// 语言头（如 `l_english:`）、空行和注释行会被跳过
+pub fn parse_entries(content: &str) -> Vec<LocalisationEntry> {
+    let content = content.trim_start_matches('\u{FEFF}');
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| parse_entry_line(line, i + 1))
+        .collect()
+}
+
+/// 原文与译文中键名相同的条目，按原文的顺序返回 `(键名, 原文, 译文)`
+///
+/// 同名键以先出现的为准，译文中没有的条目跳过
+pub(crate) fn paired_entries<'a>(
+    original: &'a str,
+    translated: &'a str,
+) -> impl Iterator<Item = (&'a str, &'a str, &'a str)> {
+    let mut translations: HashMap<&str, &str> = HashMap::new();
+    for (key, _, value) in entry_parts(translated) {
+        translations.entry(key).or_insert(value);
+    }
+    let mut seen = HashSet::new();
+    entry_parts(original).filter_map(move |(key, _, value)| {
+        if !seen.insert(key) {
+            return None;
+        }
+        translations
+            .get(key)
+            .map(|translated| (key, value, *translated))
+    })
+}
+
+/// 逐行解析条目的键名、追踪号与值，不复制文本
+fn entry_parts(content: &str) -> impl Iterator<Item = (&str, Option<u32>, &str)> {
+    content
+        .trim_start_matches('\u{FEFF}')
+        .lines()
+        .filter_map(line_parts)
+}
+
+/// 解析单行条目，不是条目的行返回 None
+pub fn parse_entry_line(line: &str, line_number: usize) -> Option<LocalisationEntry> {
+    let (key, version, value) = line_parts(line)?;
+    Some(LocalisationEntry {
+        key: key.to_string(),
+        version,
+        value: value.to_string(),
+        line: line_number,
+    })
+}
+
+/// 单行条目的键名、追踪号与值
+fn line_parts(line: &str) -> Option<(&str, Option<u32>, &str)> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let (key, rest) = trimmed.split_once(':')?;
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    let digits_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let version = rest[..digits_end].parse().ok();
+    let rest = rest[digits_end..].trim();
+    if rest.is_empty() {
+        // 语言头 l_english: 或没有值的行
+        return None;
+    }
+
+    let value = match rest.strip_prefix('"') {
+        Some(quoted) => match closing_quote(quoted) {
+            Some(end) => &quoted[..end],
+            None => quoted,
+        },
+        None => rest,
+    };
+    Some((key, version, value))
+}
+
+/// 值的结尾引号在 `text`（左引号之后的部分）中的字节位置，没有结尾引号时返回 None
+///
+/// 从左向右扫描未转义的引号并记录是否位于引号内，遇到引号外的 `#` 即为行尾注释；
+/// 结尾引号为注释之前最后一个未转义的引号，因此值内未转义的引号仍属于值，
+/// 而注释中的引号不会被当作值的一部分
+pub fn closing_quote(text: &str) -> Option<usize> {
+    let mut inside = true;
+    let mut escaped = false;
+    let mut end = None;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '"' if !escaped => {
+                inside = !inside;
+                end = Some(i);
+            }
+            '#' if !inside => break,
+            _ => escaped = false,
+        }
+    }
+    end
+}
+
+/// 条目行中引号内的值的字节范围，不是条目或没有结尾引号时返回 None
+pub fn value_range(line: &str) -> Option<Range<usize>> {
+    if line.trim_start().starts_with('#') {
+        return None;
+    }
+    let start = line.find('"')? + 1;
+    let end = start + closing_quote(&line[start..])?;
+    Some(start..end)
+}
+
+/// 读取文件并解析其中的条目
+pub fn read_entries(path: &Path) -> Result<Vec<LocalisationEntry>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_entries(&content))
+}
+
+/// 递归查找目录下的本地化文件（.yml/.yaml），按路径排序
+pub fn find_localisation_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = crate::utils::find_yaml_files(dir)?;
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries_skips_header_and_comments() {
+        let content = "\u{FEFF}l_english:\n # comment\n key:0 \"Hello\"\n\n";
+        let entries = parse_entries(content);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "key");
+        assert_eq!(entries[0].version, Some(0));
+        assert_eq!(entries[0].line, 3);
+    }
+
+    #[test]
+    fn test_parse_entry_with_inner_quotes() {
+        let entry = parse_entry_line(" key:0 \"Hello \"world\"\" # trailing", 1).unwrap();
+        assert_eq!(entry.value, "Hello \"world\"");
+    }
+
+    #[test]
+    fn test_parse_entry_without_version() {
+        let entry = parse_entry_line(" other: \"no version\"", 1).unwrap();
+        assert_eq!(entry.version, None);
+        assert_eq!(entry.value, "no version");
+    }
+
+    #[test]
+    fn test_parse_unterminated_value() {
+        let entry = parse_entry_line(" broken:1 \"unterminated", 1).unwrap();
+        assert_eq!(entry.value, "unterminated");
+    }
+
+    #[test]
+    fn test_quotes_in_trailing_comment() {
+        let entry = parse_entry_line(r#" key:0 "text" # "note""#, 1).unwrap();
+        assert_eq!(entry.value, "text");
+    }
+
+    #[test]
+    fn test_value_range_keeps_hash_inside_value() {
+        let line = r#" key:0 "a \"b\" #c" # d"#;
+        assert_eq!(&line[value_range(line).unwrap()], r#"a \"b\" #c"#);
+    }
+
+    #[test]
+    fn test_value_range_of_comment_line() {
+        assert_eq!(value_range(r#" # key:0 "text""#), None);
+    }
+
+    #[test]
+    fn test_paired_entries_first_definition_wins() {
+        let original = " a:0 \"A\"\n a:0 \"A2\"\n b:0 \"B\"\n";
+        let translated = " b:0 \"乙\"\n a:0 \"甲\"\n a:0 \"甲2\"\n";
+        let pairs: Vec<_> = paired_entries(original, translated).collect();
+        assert_eq!(pairs, [("a", "A", "甲"), ("b", "B", "乙")]);
+    }
+
+    #[test]
+    fn test_paired_entries_skip_comments_and_missing_keys() {
+        let original = "l_english:\n # c:0 \"C\"\n a:0 \"A\" # note\n b:0 \"B\"\n";
+        let translated = "l_simp_chinese:\n a:0 \"甲\"\n";
+        let pairs: Vec<_> = paired_entries(original, translated).collect();
+        assert_eq!(pairs, [("a", "A", "甲")]);
+    }
+}
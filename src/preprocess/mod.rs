@@ -2,10 +2,12 @@
 //!
 //! 负责清洗和整理原始本地化文件，修复YAML格式问题，并将大文件切片。
 
+mod entries;
 mod file_prepare;
 mod normalizer;
 mod yaml_fixer;
 
+pub use entries::*;
 pub use file_prepare::*;
 pub use normalizer::*;
 pub use yaml_fixer::*;
@@ -14,7 +14,7 @@ pub fn fix_yaml_content(content: &str) -> Result<String> {
     // 处理 key: value 的形式，value 可能有两个引号，或只有一侧有引号，或没有引号
     let re_unquoted_value = Regex::new(r#"^(\w+):\s+"?([^"]*)"?$"#).unwrap();
 
-    let lines = fixed.lines().into_iter().map(|line| {
+    let lines = fixed.lines().map(|line| {
         // 0. 跳过空行和注释行
         if line.trim().is_empty() {
             return "".into();
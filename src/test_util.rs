@@ -0,0 +1,20 @@
+//! 测试共用的辅助函数
+
+use std::path::Path;
+use tempfile::TempDir;
+
+/// 在临时目录中写入一组文件，路径相对于临时目录
+pub fn temp_tree(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, content) in files {
+        write_file(dir.path(), path, content);
+    }
+    dir
+}
+
+/// 写入文件，并创建缺少的上级目录
+pub fn write_file(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
@@ -46,7 +46,7 @@ impl ApiClient {
 
         let response = self
             .client
-            .post(&self.settings.chat_completions_url())
+            .post(self.settings.chat_completions_url())
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&request)
@@ -0,0 +1,202 @@
+//! 禁止翻译列表模块
+//!
+//! 派系名、背景设定中的专名、MOD 品牌名、制作人员名单等文本必须原样保留。
+//! 发送给大模型前将这些文本替换为 `⟦N⟧` 占位符，收到译文后再还原。
+
+use crate::error::{ConfigError, Result};
+use crate::preprocess::value_range;
+use regex::Regex;
+
+/// 数据目录中的禁止翻译列表文件
+pub const DO_NOT_TRANSLATE_FILE: &str = "do_not_translate.txt";
+
+/// 正则条目的前缀，其余条目按字面匹配
+const REGEX_PREFIX: &str = "re:";
+
+/// 禁止翻译列表
+#[derive(Debug, Clone, Default)]
+pub struct DoNotTranslate {
+    /// (原始条目, 匹配模式)
+    patterns: Vec<(String, Regex)>,
+}
+
+/// 替换为占位符后的文本
+#[derive(Debug, Clone, Default)]
+pub struct MaskedText {
+    /// 替换后的文本
+    pub text: String,
+    /// 按占位符编号（从 1 开始）排列的原文
+    pub originals: Vec<String>,
+}
+
+impl MaskedText {
+    /// 将文本中的占位符还原为原文，无法识别的占位符保持不变
+    pub fn unmask(&self, text: &str) -> String {
+        if self.originals.is_empty() {
+            return text.to_string();
+        }
+        let placeholder = Regex::new(r"⟦(\d+)⟧").unwrap();
+        placeholder
+            .replace_all(text, |caps: &regex::Captures| {
+                caps[1]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|i| self.originals.get(i))
+                    .cloned()
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .to_string()
+    }
+}
+
+impl DoNotTranslate {
+    /// 从条目列表创建，`re:` 开头的条目为正则表达式，其余按字面匹配
+    ///
+    /// 空白条目和 `#` 开头的注释会被忽略
+    pub fn from_entries<S: AsRef<str>>(entries: &[S]) -> Result<Self> {
+        let mut patterns = Vec::new();
+        for entry in entries {
+            let entry = entry.as_ref().trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            let pattern = match entry.strip_prefix(REGEX_PREFIX) {
+                Some(regex) => regex.to_string(),
+                None => literal_pattern(entry),
+            };
+            let regex = Regex::new(&pattern).map_err(|e| {
+                ConfigError::InvalidValue(format!(
+                    "Invalid do-not-translate entry '{}': {}",
+                    entry, e
+                ))
+            })?;
+            patterns.push((entry.to_string(), regex));
+        }
+        Ok(Self { patterns })
+    }
+
+    /// 加载数据目录中的 do_not_translate.txt 与任务配置中的 do_not_translate 列表
+    pub fn load(task: &crate::config::TranslationTask) -> Result<Self> {
+        let mut entries: Vec<String> = Vec::new();
+        if let Some(path) = crate::utils::find_data_file(DO_NOT_TRANSLATE_FILE)? {
+            let content = std::fs::read_to_string(&path)?;
+            entries.extend(content.lines().map(|line| line.to_string()));
+            log::info!("Loaded do-not-translate list from {}", path.display());
+        }
+        entries.extend(task.do_not_translate.iter().cloned());
+        Self::from_entries(&entries)
+    }
+
+    /// 列表是否为空
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// 查找文本中受保护的区间，按位置排序且互不重叠（重叠时保留先出现、较长的）
+    pub fn find(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = self
+            .patterns
+            .iter()
+            .flat_map(|(_, regex)| regex.find_iter(text))
+            .filter(|m| !m.is_empty())
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        spans.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+        let mut result: Vec<(usize, usize)> = Vec::new();
+        for span in spans {
+            if result.last().is_none_or(|last| span.0 >= last.1) {
+                result.push(span);
+            }
+        }
+        result
+    }
+
+    /// 整个文本是否都受保护
+    pub fn is_protected(&self, text: &str) -> bool {
+        self.find(text) == [(0, text.len())]
+    }
+
+    /// 将本地化内容中条目值里的受保护文本替换为占位符，键名和注释保持不变
+    pub fn mask(&self, content: &str) -> MaskedText {
+        let mut masked = MaskedText::default();
+        if self.patterns.is_empty() {
+            masked.text = content.to_string();
+            return masked;
+        }
+        for line in content.split_inclusive('\n') {
+            let Some(value_range) = value_range(line) else {
+                masked.text.push_str(line);
+                continue;
+            };
+            let value = &line[value_range.clone()];
+            let mut last = 0;
+            masked.text.push_str(&line[..value_range.start]);
+            for (start, end) in self.find(value) {
+                masked.text.push_str(&value[last..start]);
+                masked.originals.push(value[start..end].to_string());
+                masked
+                    .text
+                    .push_str(&format!("⟦{}⟧", masked.originals.len()));
+                last = end;
+            }
+            masked.text.push_str(&value[last..]);
+            masked.text.push_str(&line[value_range.end..]);
+        }
+        masked
+    }
+}
+
+/// 字面条目的匹配模式：以单词字符开头或结尾时按单词边界匹配，避免命中更长的单词
+fn literal_pattern(literal: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut pattern = regex::escape(literal);
+    if is_word(literal.chars().next()) {
+        pattern.insert_str(0, r"\b");
+    }
+    if is_word(literal.chars().last()) {
+        pattern.push_str(r"\b");
+    }
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dnt() -> DoNotTranslate {
+        DoNotTranslate::from_entries(&["# comment", "Zro", "Fungal Sanctuary", r"re:Team \w+"])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mask_whole_words_and_patterns() {
+        let content = " sr_zro:0 \"Zro flows from the Fungal Sanctuary. Zrofoo\"\n credits:0 \"Made by Team Spore\"\n";
+        let masked = dnt().mask(content);
+        assert_eq!(
+            masked.text,
+            " sr_zro:0 \"⟦1⟧ flows from the ⟦2⟧. Zrofoo\"\n credits:0 \"Made by ⟦3⟧\"\n"
+        );
+        assert_eq!(masked.originals, ["Zro", "Fungal Sanctuary", "Team Spore"]);
+    }
+
+    #[test]
+    fn test_unmask_keeps_unknown_placeholders() {
+        let masked = dnt().mask(" credits:0 \"Made by Team Spore\"\n");
+        assert_eq!(
+            masked.unmask(" credits:0 \"制作：⟦1⟧ ⟦9⟧\"\n"),
+            " credits:0 \"制作：Team Spore ⟦9⟧\"\n"
+        );
+    }
+
+    #[test]
+    fn test_is_protected_matches_whole_value() {
+        assert!(dnt().is_protected("Fungal Sanctuary"));
+        assert!(!dnt().is_protected("Sanctuary"));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        assert!(DoNotTranslate::from_entries(&["re:("]).is_err());
+    }
+}
@@ -2,19 +2,30 @@
 //!
 //! 加载和管理翻译术语表。每个术语表提供多语言对照。
 
+use crate::config::{GlossaryRef, GlossaryScope};
 use crate::error::{Result, TranslationError};
 use serde::de::Error as SerdeError;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 /// 多语言术语条目
 ///
 /// 字段按语言使用量排序，使用数字键名进行序列化/反序列化以节省空间：
 /// 1: english, 2: simp_chinese, 3: spanish, 4: french, 5: braz_por,
 /// 6: russian, 7: german, 8: japanese, 9: korean, 10: polish
-/// 反序列化属性写在后文的 RawItem 结构体中。
-#[derive(Debug, Clone, Serialize)]
+///
+/// 每种语言的值可以是一个字符串，也可以是带有说明、其他可接受译法与禁止译法的对象：
+/// ```json
+/// {
+///   "1": "energy",
+///   "2": {"term": "能量", "note": "指资源", "alternatives": ["能源"], "forbidden": ["精力"]},
+///   "pos": "noun",
+///   "case_sensitive": false
+/// }
+/// ```
+/// 序列化与反序列化的实现写在后文中，反序列化属性写在 RawItem 结构体中。
+#[derive(Debug, Clone, Default)]
 pub struct GlossaryItem {
     pub english: Option<String>,      // 1
     pub simp_chinese: Option<String>, // 2
@@ -26,6 +37,48 @@ pub struct GlossaryItem {
     pub japanese: Option<String>,     // 8
     pub korean: Option<String>,       // 9
     pub polish: Option<String>,       // 10
+    /// 条目备注，不属于任何语言，导入/导出时保留
+    pub note: Option<String>,
+    /// 各语言术语的用法说明：语言 -> 说明
+    pub notes: BTreeMap<String, String>,
+    /// 其他可接受的译法：语言 -> 译法列表
+    pub alternatives: BTreeMap<String, Vec<String>>,
+    /// 禁止使用的译法：语言 -> 译法列表
+    pub forbidden: BTreeMap<String, Vec<String>>,
+    /// 词性（如 noun、verb、adjective）
+    pub pos: Option<String>,
+    /// 在源文本中查找该术语时是否区分大小写，默认不区分
+    pub case_sensitive: bool,
+}
+
+/// 术语表支持的语言，顺序与数字键名一致
+pub const GLOSSARY_LANGUAGES: [&str; 10] = [
+    "english",
+    "simp_chinese",
+    "spanish",
+    "french",
+    "braz_por",
+    "russian",
+    "german",
+    "japanese",
+    "korean",
+    "polish",
+];
+
+/// 单个语言的术语，可以是字符串或扩展形式的对象
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTerm {
+    Plain(String),
+    Extended {
+        term: String,
+        #[serde(default)]
+        note: Option<String>,
+        #[serde(default)]
+        alternatives: Vec<String>,
+        #[serde(default)]
+        forbidden: Vec<String>,
+    },
 }
 
 impl<'de> Deserialize<'de> for GlossaryItem {
@@ -36,59 +89,132 @@ impl<'de> Deserialize<'de> for GlossaryItem {
         #[derive(Deserialize)]
         struct RawItem {
             #[serde(rename = "1", default)]
-            english: Option<String>,
+            english: Option<RawTerm>,
             #[serde(rename = "2", default)]
-            simp_chinese: Option<String>,
+            simp_chinese: Option<RawTerm>,
             #[serde(rename = "3", default)]
-            spanish: Option<String>,
+            spanish: Option<RawTerm>,
             #[serde(rename = "4", default)]
-            french: Option<String>,
+            french: Option<RawTerm>,
             #[serde(rename = "5", default)]
-            braz_por: Option<String>,
+            braz_por: Option<RawTerm>,
             #[serde(rename = "6", default)]
-            russian: Option<String>,
+            russian: Option<RawTerm>,
             #[serde(rename = "7", default)]
-            german: Option<String>,
+            german: Option<RawTerm>,
             #[serde(rename = "8", default)]
-            japanese: Option<String>,
+            japanese: Option<RawTerm>,
             #[serde(rename = "9", default)]
-            korean: Option<String>,
+            korean: Option<RawTerm>,
             #[serde(rename = "10", default)]
-            polish: Option<String>,
+            polish: Option<RawTerm>,
+            #[serde(default)]
+            note: Option<String>,
+            #[serde(default)]
+            pos: Option<String>,
+            #[serde(default)]
+            case_sensitive: bool,
         }
 
         let raw = RawItem::deserialize(deserializer)?;
+        let terms = [
+            ("english", raw.english),
+            ("simp_chinese", raw.simp_chinese),
+            ("spanish", raw.spanish),
+            ("french", raw.french),
+            ("braz_por", raw.braz_por),
+            ("russian", raw.russian),
+            ("german", raw.german),
+            ("japanese", raw.japanese),
+            ("korean", raw.korean),
+            ("polish", raw.polish),
+        ];
 
         // 检查是否至少有一个字段有值
-        let has_value = raw.english.is_some()
-            || raw.simp_chinese.is_some()
-            || raw.spanish.is_some()
-            || raw.french.is_some()
-            || raw.braz_por.is_some()
-            || raw.russian.is_some()
-            || raw.german.is_some()
-            || raw.japanese.is_some()
-            || raw.korean.is_some()
-            || raw.polish.is_some();
-
-        if !has_value {
+        if terms.iter().all(|(_, term)| term.is_none()) {
             return Err(<D as serde::Deserializer<'de>>::Error::custom(
                 "GlossaryItem must contain at least one language field",
             ));
         }
 
-        Ok(GlossaryItem {
-            english: raw.english,
-            simp_chinese: raw.simp_chinese,
-            spanish: raw.spanish,
-            french: raw.french,
-            braz_por: raw.braz_por,
-            russian: raw.russian,
-            german: raw.german,
-            japanese: raw.japanese,
-            korean: raw.korean,
-            polish: raw.polish,
-        })
+        let mut item = GlossaryItem {
+            note: raw.note,
+            pos: raw.pos,
+            case_sensitive: raw.case_sensitive,
+            ..Default::default()
+        };
+        for (lang, term) in terms {
+            let term = match term {
+                None => continue,
+                Some(RawTerm::Plain(term)) => term,
+                Some(RawTerm::Extended {
+                    term,
+                    note,
+                    alternatives,
+                    forbidden,
+                }) => {
+                    if let Some(note) = note {
+                        item.notes.insert(lang.to_string(), note);
+                    }
+                    if !alternatives.is_empty() {
+                        item.alternatives.insert(lang.to_string(), alternatives);
+                    }
+                    if !forbidden.is_empty() {
+                        item.forbidden.insert(lang.to_string(), forbidden);
+                    }
+                    term
+                }
+            };
+            item.set(lang, Some(term)).map_err(D::Error::custom)?;
+        }
+        Ok(item)
+    }
+}
+
+impl Serialize for GlossaryItem {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        for (i, lang) in GLOSSARY_LANGUAGES.iter().enumerate() {
+            let Some(term) = self.get(lang) else {
+                continue;
+            };
+            let key = (i + 1).to_string();
+            let note = self.notes.get(*lang);
+            let alternatives = self.alternatives(lang);
+            let forbidden = self.forbidden(lang);
+            if note.is_none() && alternatives.is_empty() && forbidden.is_empty() {
+                map.serialize_entry(&key, term)?;
+                continue;
+            }
+            // 有附加信息时使用扩展形式
+            let mut extended = serde_json::Map::new();
+            extended.insert("term".to_string(), term.into());
+            if let Some(note) = note {
+                extended.insert("note".to_string(), note.as_str().into());
+            }
+            if !alternatives.is_empty() {
+                extended.insert("alternatives".to_string(), alternatives.into());
+            }
+            if !forbidden.is_empty() {
+                extended.insert("forbidden".to_string(), forbidden.into());
+            }
+            map.serialize_entry(&key, &extended)?;
+        }
+        if let Some(note) = &self.note {
+            map.serialize_entry("note", note)?;
+        }
+        if let Some(pos) = &self.pos {
+            map.serialize_entry("pos", pos)?;
+        }
+        if self.case_sensitive {
+            map.serialize_entry("case_sensitive", &true)?;
+        }
+        map.end()
     }
 }
 
@@ -115,6 +241,93 @@ impl GlossaryItem {
         self.get(lang).is_some()
     }
 
+    /// 设置指定语言的术语，传入 None 则清除该语言
+    ///
+    /// 不支持的语言返回错误
+    pub fn set(&mut self, lang: &str, term: Option<String>) -> Result<()> {
+        let slot = match lang {
+            "english" => &mut self.english,
+            "simp_chinese" => &mut self.simp_chinese,
+            "spanish" => &mut self.spanish,
+            "french" => &mut self.french,
+            "braz_por" => &mut self.braz_por,
+            "russian" => &mut self.russian,
+            "german" => &mut self.german,
+            "japanese" => &mut self.japanese,
+            "korean" => &mut self.korean,
+            "polish" => &mut self.polish,
+            _ => {
+                return Err(glossary_error(format!(
+                    "Unsupported glossary language '{}', expected one of: {}",
+                    lang,
+                    GLOSSARY_LANGUAGES.join(", ")
+                )));
+            }
+        };
+        *slot = term;
+        Ok(())
+    }
+
+    /// 获取指定语言的其他可接受译法
+    pub fn alternatives(&self, lang: &str) -> &[String] {
+        self.alternatives
+            .get(lang)
+            .map(|terms| terms.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 获取指定语言禁止使用的译法
+    pub fn forbidden(&self, lang: &str) -> &[String] {
+        self.forbidden
+            .get(lang)
+            .map(|terms| terms.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 获取指定语言术语的用法说明
+    pub fn lang_note(&self, lang: &str) -> Option<&str> {
+        self.notes.get(lang).map(|note| note.as_str())
+    }
+
+    /// 是否有表格与 TBX 导入导出无法保留的信息：用法说明、其他译法、禁止译法、词性或区分大小写
+    pub fn has_extended_fields(&self) -> bool {
+        !self.notes.is_empty()
+            || !self.alternatives.is_empty()
+            || !self.forbidden.is_empty()
+            || self.pos.is_some()
+            || self.case_sensitive
+    }
+
+    /// 添加指定语言的其他可接受译法，与主译法或已有译法相同时忽略
+    pub fn add_alternative(&mut self, lang: &str, term: &str) {
+        if self.get(lang) == Some(term) || self.alternatives(lang).iter().any(|t| t == term) {
+            return;
+        }
+        self.alternatives
+            .entry(lang.to_string())
+            .or_default()
+            .push(term.to_string());
+    }
+
+    /// 面向目标语言的说明文本：词性、备注、用法说明与禁止译法，以中文分号分隔
+    pub fn remarks(&self, target_lang: &str) -> String {
+        let mut remarks = Vec::new();
+        if let Some(pos) = &self.pos {
+            remarks.push(format!("词性：{}", pos));
+        }
+        if let Some(note) = &self.note {
+            remarks.push(note.clone());
+        }
+        if let Some(note) = self.lang_note(target_lang) {
+            remarks.push(note.to_string());
+        }
+        let forbidden = self.forbidden(target_lang);
+        if !forbidden.is_empty() {
+            remarks.push(format!("禁止译为：{}", forbidden.join("、")));
+        }
+        remarks.join("；")
+    }
+
     /// 获取所有有值的语言和术语
     pub fn all_terms(&self) -> Vec<(&'static str, &str)> {
         let mut terms = Vec::new();
@@ -152,11 +365,58 @@ impl GlossaryItem {
     }
 }
 
+/// 术语表文件的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlossaryKind {
+    /// 随程序发布的默认术语表（glossary/）
+    Default,
+    /// 用户自定义术语表（glossary_custom/）
+    Custom,
+}
+
+impl std::fmt::Display for GlossaryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlossaryKind::Default => write!(f, "default"),
+            GlossaryKind::Custom => write!(f, "custom"),
+        }
+    }
+}
+
+/// 术语条目的来源文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlossaryOrigin {
+    /// 术语表名称（不带 .json 扩展名）
+    pub name: String,
+    pub kind: GlossaryKind,
+    pub path: PathBuf,
+    /// 术语表所属层级
+    pub scope: GlossaryScope,
+    /// 优先级，数值大的覆盖数值小的
+    pub priority: i32,
+}
+
+impl std::fmt::Display for GlossaryOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}, priority {}] ({}: {})",
+            self.name,
+            self.scope,
+            self.priority,
+            self.kind,
+            self.path.display()
+        )
+    }
+}
+
 /// 术语表
 #[derive(Debug, Clone, Default)]
 pub struct Glossary {
     /// 术语索引：key -> GlossaryItem
     entries: HashMap<String, GlossaryItem>,
+    /// 条目来源：key -> GlossaryOrigin，仅记录通过 with_origin 标注过的条目
+    origins: HashMap<String, GlossaryOrigin>,
 }
 
 impl Glossary {
@@ -170,6 +430,18 @@ impl Glossary {
     /// }
     /// ```
     pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load_json_file(path.as_ref(), false)
+    }
+
+    /// 从JSON文件加载要修改并写回的术语表
+    ///
+    /// 与 [`Glossary::from_json_file`] 不同，任何条目无法解析时都返回错误，
+    /// 以免写回时丢失这些条目
+    pub fn from_json_file_strict<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::load_json_file(path.as_ref(), true)
+    }
+
+    fn load_json_file(path: &Path, strict: bool) -> Result<Self> {
         let content = std::fs::read_to_string(path).map_err(|e| {
             TranslationError::Translate(crate::error::TranslateError::GlossaryError(e.to_string()))
         })?;
@@ -187,6 +459,14 @@ impl Glossary {
                         Ok(glossary_item) => {
                             entries.insert(key, glossary_item);
                         }
+                        Err(e) if strict => {
+                            return Err(glossary_error(format!(
+                                "无法解析术语表 {} 的条目 {}: {}",
+                                path.display(),
+                                key,
+                                e
+                            )));
+                        }
                         Err(e) => {
                             // 无法解析的值，记录警告并跳过
                             log::warn!("无法解析术语表条目: key={}, error={}", key, e);
@@ -203,7 +483,114 @@ impl Glossary {
             }
         }
 
-        Ok(Self { entries })
+        Ok(Self {
+            entries,
+            origins: HashMap::new(),
+        })
+    }
+
+    /// 将术语表保存为JSON文件
+    ///
+    /// 条目按 key 排序输出，保存前会先校验所有条目
+    pub fn save_json_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.validate()?;
+        let sorted: BTreeMap<&String, &GlossaryItem> = self.entries.iter().collect();
+        let mut content =
+            serde_json::to_string_pretty(&sorted).map_err(|e| glossary_error(e.to_string()))?;
+        content.push('\n');
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 校验所有条目
+    ///
+    /// key 不能为空且必须为小写（查找术语时不区分大小写），每个条目至少包含一种语言，
+    /// 且术语不能为空字符串
+    pub fn validate(&self) -> Result<()> {
+        for (key, item) in &self.entries {
+            validate_entry(key, item)?;
+        }
+        Ok(())
+    }
+
+    /// 标注所有条目的来源
+    pub fn with_origin(mut self, origin: GlossaryOrigin) -> Self {
+        self.origins = self
+            .entries
+            .keys()
+            .map(|key| (key.clone(), origin.clone()))
+            .collect();
+        self
+    }
+
+    /// 获取条目的来源
+    pub fn origin(&self, key: &str) -> Option<&GlossaryOrigin> {
+        self.origins.get(key)
+    }
+
+    /// 获取单个条目
+    pub fn get(&self, key: &str) -> Option<&GlossaryItem> {
+        self.entries.get(key)
+    }
+
+    /// 插入或替换条目，返回被替换的旧条目
+    ///
+    /// 插入前会校验条目，新条目不携带来源信息
+    pub fn insert(&mut self, key: String, item: GlossaryItem) -> Result<Option<GlossaryItem>> {
+        validate_entry(&key, &item)?;
+        self.origins.remove(&key);
+        Ok(self.entries.insert(key, item))
+    }
+
+    /// 获取条目的可变引用
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut GlossaryItem> {
+        self.entries.get_mut(key)
+    }
+
+    /// 插入条目并记录其来源，不做校验，用于合并已加载的术语表
+    pub(crate) fn insert_with_origin(
+        &mut self,
+        key: String,
+        item: GlossaryItem,
+        origin: Option<GlossaryOrigin>,
+    ) {
+        match origin {
+            Some(origin) => self.origins.insert(key.clone(), origin),
+            None => self.origins.remove(&key),
+        };
+        self.entries.insert(key, item);
+    }
+
+    /// 移除条目
+    pub fn remove(&mut self, key: &str) -> Option<GlossaryItem> {
+        self.origins.remove(key);
+        self.entries.remove(key)
+    }
+
+    /// 搜索 key 或术语中包含 query 的条目（不区分大小写），结果按 key 排序
+    ///
+    /// 指定 lang 时只匹配该语言的术语
+    pub fn search(&self, query: &str, lang: Option<&str>) -> Vec<(&str, &GlossaryItem)> {
+        let query = query.to_lowercase();
+        let mut found: Vec<(&str, &GlossaryItem)> = self
+            .entries
+            .iter()
+            .filter(|(key, item)| {
+                let key_matches = lang.is_none() && key.to_lowercase().contains(&query);
+                let term_matches = item
+                    .all_terms()
+                    .iter()
+                    .filter(|(l, _)| lang.is_none_or(|lang| lang == *l))
+                    .any(|(_, term)| term.to_lowercase().contains(&query));
+                key_matches || term_matches
+            })
+            .map(|(key, item)| (key.as_str(), item))
+            .collect();
+        found.sort_by_key(|(key, _)| *key);
+        found
     }
 
     /// 获取源语言到目标语言的翻译映射
@@ -215,11 +602,11 @@ impl Glossary {
         target_lang: &str,
     ) -> HashMap<String, String> {
         let mut map = HashMap::new();
-        for (_key, item) in &self.entries {
-            if let Some(source_term) = item.get(source_lang) {
-                if let Some(target_term) = item.get(target_lang) {
-                    map.insert(source_term.to_string(), target_term.to_string());
-                }
+        for item in self.entries.values() {
+            if let Some(source_term) = item.get(source_lang)
+                && let Some(target_term) = item.get(target_lang)
+            {
+                map.insert(source_term.to_string(), target_term.to_string());
             }
         }
         map
@@ -267,32 +654,71 @@ impl Glossary {
     /// energy,能量
     /// minerals,矿物
     /// ```
-    pub fn to_csv(&self, source_lang: &str, target_lang: &str, source_words: &[&str]) -> String {
-        let mut wtr = String::with_capacity(1024);
-        // header
-        wtr.push_str(&format!("{},{}\n", source_lang, target_lang));
-
-        for word in source_words {
-            if let Some(item) = self.entries.get(*word) {
-                if let Some(source_term) = item.get(source_lang) {
-                    if let Some(target_term) = item.get(target_lang) {
-                        wtr.push_str(&format!("{},{}\n", source_term, target_term));
+    ///
+    /// keys 为条目的 key。目标语言有其他可接受的译法时，以 " / " 分隔列在主译法之后；
+    /// 任一条目有词性、备注或禁止译法时，增加第三列“说明”
+    pub fn to_csv(&self, source_lang: &str, target_lang: &str, keys: &[&str]) -> String {
+        let mut rows = Vec::new();
+        for key in keys {
+            if let Some(item) = self.entries.get(*key)
+                && let Some(source_term) = item.get(source_lang)
+                && let Some(target_term) = item.get(target_lang)
+            {
+                let mut targets = vec![target_term];
+                targets.extend(item.alternatives(target_lang).iter().map(|t| t.as_str()));
+                let mut remarks = item.remarks(target_lang);
+                // 非游戏本体层级的术语注明来源，便于大模型理解其适用范围
+                if let Some(origin) = self.origin(key)
+                    && origin.scope != GlossaryScope::Game
+                {
+                    if !remarks.is_empty() {
+                        remarks.push('；');
                     }
+                    remarks.push_str(&format!("来源：{}（{}）", origin.name, origin.scope));
                 }
+                rows.push((source_term, targets.join(" / "), remarks));
+            }
+        }
+
+        let with_remarks = rows.iter().any(|(_, _, remarks)| !remarks.is_empty());
+        let mut wtr = String::with_capacity(1024);
+        // header
+        if with_remarks {
+            wtr.push_str(&format!("{},{},说明\n", source_lang, target_lang));
+        } else {
+            wtr.push_str(&format!("{},{}\n", source_lang, target_lang));
+        }
+        for (source_term, targets, remarks) in rows {
+            if with_remarks {
+                wtr.push_str(&format!(
+                    "{},{},{}\n",
+                    source_term,
+                    targets,
+                    csv_cell(&remarks)
+                ));
+            } else {
+                wtr.push_str(&format!("{},{}\n", source_term, targets));
             }
         }
 
         wtr
     }
 
-    /// 发现待翻译文本中存在的术语表条目
+    /// 发现待翻译文本中存在的术语表条目，返回条目的 key
+    ///
+    /// 默认不区分大小写，case_sensitive 的条目按原样匹配
     pub fn find_terms_in_text(&self, text: &str, source_lang: &str) -> Vec<String> {
         let mut found_terms = Vec::new();
-        let text = text.to_lowercase();
-        for (_key, item) in &self.entries {
+        let lowercase_text = text.to_lowercase();
+        for (key, item) in &self.entries {
             if let Some(source_term) = item.get(source_lang) {
-                if text.contains(source_term) {
-                    found_terms.push(source_term.to_string());
+                let found = if item.case_sensitive {
+                    text.contains(source_term)
+                } else {
+                    lowercase_text.contains(&source_term.to_lowercase())
+                };
+                if found {
+                    found_terms.push(key.clone());
                 }
             }
         }
@@ -302,81 +728,212 @@ impl Glossary {
     /// 合并多个术语表到一个术语表
     pub fn merge_glossaries(glossaries: &[Glossary]) -> Glossary {
         let mut merged_entries = HashMap::new();
+        let mut merged_origins = HashMap::new();
         for glossary in glossaries {
             for (key, item) in &glossary.entries {
                 merged_entries.insert(key.clone(), item.clone());
+                match glossary.origins.get(key) {
+                    Some(origin) => merged_origins.insert(key.clone(), origin.clone()),
+                    None => merged_origins.remove(key),
+                };
             }
         }
         Glossary {
             entries: merged_entries,
+            origins: merged_origins,
+        }
+    }
+}
+
+/// 含有逗号或引号的 CSV 单元格需要加引号
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn glossary_error(message: String) -> TranslationError {
+    TranslationError::Translate(crate::error::TranslateError::GlossaryError(message))
+}
+
+/// 校验单个术语条目
+pub(crate) fn validate_entry(key: &str, item: &GlossaryItem) -> Result<()> {
+    if key.trim().is_empty() {
+        return Err(glossary_error("Glossary key must not be empty".to_string()));
+    }
+    if key != key.to_lowercase() {
+        return Err(glossary_error(format!(
+            "Glossary key '{}' must be lowercase",
+            key
+        )));
+    }
+    let terms = item.all_terms();
+    if terms.is_empty() {
+        return Err(glossary_error(format!(
+            "Glossary entry '{}' must contain at least one language",
+            key
+        )));
+    }
+    if let Some((lang, _)) = terms.iter().find(|(_, term)| term.trim().is_empty()) {
+        return Err(glossary_error(format!(
+            "Glossary entry '{}' has an empty {} term",
+            key, lang
+        )));
+    }
+    // 说明、其他译法与禁止译法只能针对已有术语的语言
+    let extra_langs = item
+        .notes
+        .keys()
+        .chain(item.alternatives.keys())
+        .chain(item.forbidden.keys());
+    for lang in extra_langs {
+        if !item.has_language(lang) {
+            return Err(glossary_error(format!(
+                "Glossary entry '{}' has notes or alternatives for {} but no {} term",
+                key, lang, lang
+            )));
+        }
+    }
+    let variants = item.alternatives.values().chain(item.forbidden.values());
+    if variants.flatten().any(|term| term.trim().is_empty()) {
+        return Err(glossary_error(format!(
+            "Glossary entry '{}' has an empty alternative or forbidden term",
+            key
+        )));
+    }
+    if item.pos.as_deref().is_some_and(|pos| pos.trim().is_empty()) {
+        return Err(glossary_error(format!(
+            "Glossary entry '{}' has an empty part of speech",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// 查找指定名称的默认术语表与自定义术语表文件，返回 (默认, 自定义)
+pub fn find_glossary_files(name: &str) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
+    use crate::utils::find_data_file;
+    let default = find_data_file(&format!("glossary/{}.json", name))?;
+    let custom = find_data_file(&format!("glossary_custom/{}.json", name))?;
+    Ok((default, custom))
+}
+
+/// 加载指定名称的术语表，默认术语表在前、自定义术语表在后，并标注各条目的来源
+///
+/// 来源中的层级为 game，优先级为 0
+pub fn load_glossary_layers(name: &str) -> Result<Vec<(GlossaryOrigin, Glossary)>> {
+    load_glossary_ref_layers(&GlossaryRef::Name(name.to_string()))
+}
+
+/// 加载任务中引用的术语表，来源中记录其层级与优先级
+pub fn load_glossary_ref_layers(
+    reference: &GlossaryRef,
+) -> Result<Vec<(GlossaryOrigin, Glossary)>> {
+    let name = reference.name();
+    let (default, custom) = find_glossary_files(name)?;
+    let mut layers = Vec::new();
+    for (kind, path) in [
+        (GlossaryKind::Default, default),
+        (GlossaryKind::Custom, custom),
+    ] {
+        if let Some(path) = path {
+            let origin = GlossaryOrigin {
+                name: name.to_string(),
+                kind,
+                path,
+                scope: reference.scope(),
+                priority: reference.priority(),
+            };
+            let glossary = Glossary::from_json_file(&origin.path)?.with_origin(origin.clone());
+            layers.push((origin, glossary));
+        }
+    }
+    Ok(layers)
+}
+
+/// 按优先级从低到高列出任务使用的所有术语表
+///
+/// 未配置 glossaries 时使用游戏配置中的默认术语表（游戏没有默认术语表时不使用术语表）；
+/// 优先级相同的按配置中的顺序排列，mod_glossary 视为 mod 层级并排在最后；
+/// 同一名称的默认术语表在前、自定义术语表在后
+pub fn task_glossary_refs(task: &crate::config::TranslationTask) -> Vec<GlossaryRef> {
+    let mut refs = task.glossaries.clone();
+    if refs.is_empty()
+        && let Some(glossary) = task.profile().glossary
+    {
+        refs.push(GlossaryRef::Name(glossary.to_string()));
+    }
+    if let Some(name) = &task.mod_glossary
+        && !refs.iter().any(|r| r.name() == name)
+    {
+        refs.push(GlossaryRef::Layer {
+            name: name.clone(),
+            scope: GlossaryScope::Mod,
+            priority: None,
+        });
+    }
+    // 稳定排序，保持同优先级的配置顺序
+    refs.sort_by_key(|r| r.priority());
+    refs
+}
+
+/// 按优先级从低到高加载任务使用的所有术语表文件
+pub fn load_task_glossary_layers(
+    task: &crate::config::TranslationTask,
+) -> Result<Vec<(GlossaryOrigin, Glossary)>> {
+    let mut layers = Vec::new();
+    for reference in task_glossary_refs(task) {
+        // 先加载默认的，再加载自定义的，后者覆盖前者
+        let found = load_glossary_ref_layers(&reference)?;
+        let is_mod_glossary = task.mod_glossary.as_deref() == Some(reference.name());
+        // MOD 专属术语表首次运行时尚不存在，由术语预翻译生成
+        if found.is_empty() && !is_mod_glossary {
+            let name = reference.name();
+            log::warn!(
+                "Glossary file not found for '{}'. Searched in:\n1. ./data/glossary_custom/{}.json\n2. ./data/glossary/{}.json",
+                name,
+                name,
+                name
+            );
         }
+        layers.extend(found);
     }
+    Ok(layers)
 }
 
-/// 从 TranslationTask.glossaries 配置中加载所有涉及的术语表，并将其合并为一个 Glossary 对象
+/// 从 TranslationTask.glossaries 配置中加载所有涉及的术语表，按优先级合并为一个 Glossary 对象
 pub fn load_glossaries_from_task(
     task: &crate::config::TranslationTask,
 ) -> Result<crate::translate::Glossary> {
-    use crate::translate::Glossary;
-    use crate::utils::find_data_file;
     let mut glossaries = Vec::new();
-    for glossary_name in &task.glossaries {
-        // 先尝试 glossary_custom 目录
-        let custom_path = format!("glossary_custom/{}.json", glossary_name);
-        let default_path = format!("glossary/{}.json", glossary_name);
-
-        let custom = find_data_file(&custom_path)?;
-        let default = find_data_file(&default_path)?;
-
-        match (custom, default) {
-            // 都不存在，则记录警告
-            (None, None) => {
-                log::warn!(
-                    "Glossary file not found for '{}'. Searched in:\n1. ./data/{}\n2. ./data/{}",
-                    glossary_name,
-                    custom_path,
-                    default_path
-                );
-            }
-            // 只存在一个，则加载该文件
-            (Some(path), None) | (None, Some(path)) => {
-                let glossary = Glossary::from_json_file(&path)?;
-                let glossary_len = glossary.len();
-                glossaries.push(glossary);
-                log::info!(
-                    "Loaded glossary '{}' with {} entries",
-                    glossary_name,
-                    glossary_len
-                );
-            }
-            // 两个都存在，则先加载默认的，再加载自定义的，后者覆盖前者
-            (Some(c), Some(d)) => {
-                let glossary = Glossary::from_json_file(&d)?;
-                let glossary_len = glossary.len();
-                glossaries.push(glossary);
-                log::info!(
-                    "Loaded glossary '{}' with {} entries",
-                    glossary_name,
-                    glossary_len
-                );
-                let glossary = Glossary::from_json_file(&c)?;
-                let glossary_len = glossary.len();
-                glossaries.push(glossary);
-                log::info!(
-                    "Loaded custom glossary '{}' with {} entries",
-                    glossary_name,
-                    glossary_len
-                );
-            }
-        }
+    for (origin, glossary) in load_task_glossary_layers(task)? {
+        log::info!(
+            "Loaded {} glossary '{}' ({}, priority {}) with {} entries",
+            origin.kind,
+            origin.name,
+            origin.scope,
+            origin.priority,
+            glossary.len()
+        );
+        glossaries.push(glossary);
+    }
+    let (merged_glossary, conflicts) = crate::translate::merge_glossaries_with_policy(
+        &glossaries,
+        &task.source_lang,
+        task.glossary_conflict_policy,
+    )?;
+    for conflict in &conflicts {
+        log::warn!("Glossary conflict: {}", conflict);
     }
-    let merged_glossary = Glossary::merge_glossaries(&glossaries);
     Ok(merged_glossary)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::temp_tree;
 
     #[test]
     fn test_glossary_item_deserialize_new_format() {
@@ -408,6 +965,12 @@ mod tests {
             japanese: None,
             korean: None,
             polish: None,
+            note: None,
+            notes: BTreeMap::new(),
+            alternatives: BTreeMap::new(),
+            forbidden: BTreeMap::new(),
+            pos: None,
+            case_sensitive: false,
         };
         assert_eq!(item.get("english"), Some("energy"));
         assert_eq!(item.get("simp_chinese"), Some("能量"));
@@ -456,6 +1019,214 @@ mod tests {
         assert_eq!(translated, "We need more 能量 and 矿物.");
     }
 
+    fn extended() -> Glossary {
+        let json = r#"{
+            "energy": {
+                "1": "energy",
+                "2": {"term": "能量", "note": "指资源，而非思潮", "alternatives": ["能源"], "forbidden": ["精力"]},
+                "note": "resource",
+                "pos": "noun"
+            },
+            "us": {"1": "US", "2": "美国", "case_sensitive": true}
+        }"#;
+        from_json_file_content(json).unwrap()
+    }
+
+    #[test]
+    fn test_glossary_extended_entry() {
+        let glossary = extended();
+        let energy = glossary.get("energy").unwrap();
+        assert_eq!(energy.get("simp_chinese"), Some("能量"));
+        assert_eq!(energy.alternatives("simp_chinese"), ["能源"]);
+        assert_eq!(energy.forbidden("simp_chinese"), ["精力"]);
+        assert_eq!(energy.lang_note("simp_chinese"), Some("指资源，而非思潮"));
+        assert_eq!(energy.pos.as_deref(), Some("noun"));
+    }
+
+    #[test]
+    fn test_glossary_extended_entry_roundtrip() {
+        let glossary = extended();
+        // 扩展形式在序列化后保持不变，普通术语仍为字符串
+        let value = serde_json::to_value(glossary.get("energy").unwrap()).unwrap();
+        assert_eq!(value["1"], "energy");
+        assert_eq!(value["2"]["alternatives"][0], "能源");
+        let reparsed: GlossaryItem = serde_json::from_value(value).unwrap();
+        assert_eq!(reparsed.forbidden("simp_chinese"), ["精力"]);
+    }
+
+    #[test]
+    fn test_glossary_case_sensitive_terms() {
+        let glossary = extended();
+        let mut found = glossary.find_terms_in_text("Tell us about Energy", "english");
+        found.sort();
+        assert_eq!(found, vec!["energy"]);
+        assert_eq!(
+            glossary.find_terms_in_text("The US fleet", "english"),
+            vec!["us"]
+        );
+    }
+
+    #[test]
+    fn test_glossary_extended_entry_in_prompt() {
+        let csv = extended().to_csv("english", "simp_chinese", &["energy", "us"]);
+        assert_eq!(
+            csv,
+            "english,simp_chinese,说明\nenergy,能量 / 能源,词性：noun；resource；指资源，而非思潮；禁止译为：精力\nUS,美国,\n"
+        );
+    }
+
+    #[test]
+    fn test_glossary_save_roundtrip_sorted() {
+        let mut glossary = Glossary::default();
+        for (key, zh) in [("minerals", "矿物"), ("energy", "能量")] {
+            let mut item = GlossaryItem::default();
+            item.set("english", Some(key.to_string())).unwrap();
+            item.set("simp_chinese", Some(zh.to_string())).unwrap();
+            glossary.insert(key.to_string(), item).unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("custom.json");
+        glossary.save_json_file(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.find("\"energy\"").unwrap() < content.find("\"minerals\"").unwrap());
+        assert!(content.contains("\"2\": \"能量\""));
+
+        let loaded = Glossary::from_json_file(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded.get("energy").unwrap().get("simp_chinese"),
+            Some("能量")
+        );
+    }
+
+    #[test]
+    fn test_glossary_strict_load_rejects_bad_entries() {
+        let dir = temp_tree(&[(
+            "custom.json",
+            r#"{"energy": {"1": "energy", "2": "能量"}, "broken": 42}"#,
+        )]);
+        let path = dir.path().join("custom.json");
+
+        assert_eq!(Glossary::from_json_file(&path).unwrap().len(), 1);
+        assert!(Glossary::from_json_file_strict(&path).is_err());
+    }
+
+    #[test]
+    fn test_glossary_insert_requires_a_term() {
+        let mut glossary = Glossary::default();
+        assert!(
+            glossary
+                .insert("energy".to_string(), GlossaryItem::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_glossary_insert_requires_lowercase_key() {
+        let mut glossary = Glossary::default();
+        let mut item = GlossaryItem::default();
+        item.set("english", Some("Energy".to_string())).unwrap();
+        assert!(glossary.insert("Energy".to_string(), item).is_err());
+    }
+
+    #[test]
+    fn test_glossary_item_rejects_unknown_language() {
+        let mut item = GlossaryItem::default();
+        assert!(item.set("klingon", Some("x".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_glossary_search() {
+        let json = r#"{
+            "energy": {"1": "energy", "2": "能量"},
+            "energy weapon": {"1": "energy weapon", "2": "能量武器"},
+            "minerals": {"1": "minerals", "2": "矿物"}
+        }"#;
+        let glossary = from_json_file_content(json).unwrap();
+        let keys: Vec<&str> = glossary
+            .search("ENERGY", None)
+            .iter()
+            .map(|(k, _)| *k)
+            .collect();
+        assert_eq!(keys, vec!["energy", "energy weapon"]);
+        assert_eq!(glossary.search("矿", Some("simp_chinese")).len(), 1);
+        assert!(glossary.search("矿", Some("english")).is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_origin_of_last_layer() {
+        let origin = |kind| GlossaryOrigin {
+            name: "stellaris".to_string(),
+            kind,
+            path: PathBuf::from("x.json"),
+            scope: GlossaryScope::Game,
+            priority: 0,
+        };
+        let default = from_json_file_content(r#"{"energy": {"1": "energy", "2": "能源"}}"#)
+            .unwrap()
+            .with_origin(origin(GlossaryKind::Default));
+        let custom = from_json_file_content(r#"{"energy": {"1": "energy", "2": "能量"}}"#)
+            .unwrap()
+            .with_origin(origin(GlossaryKind::Custom));
+        let merged = Glossary::merge_glossaries(&[default, custom]);
+        assert_eq!(
+            merged.get("energy").unwrap().get("simp_chinese"),
+            Some("能量")
+        );
+        assert_eq!(merged.origin("energy").unwrap().kind, GlossaryKind::Custom);
+    }
+
+    #[test]
+    fn test_task_glossary_layer_order() {
+        let task: crate::config::TranslationTask = toml::from_str(
+            r#"
+source_lang = "english"
+target_langs = ["simp_chinese"]
+localisation_dir = "localisation"
+mod_glossary = "my_mod"
+glossaries = [
+    { name = "family", scope = "mod_family" },
+    "stellaris",
+    { name = "dlc_overlord", scope = "dlc" },
+    { name = "patch", scope = "mod", priority = 400 },
+]
+"#,
+        )
+        .unwrap();
+        let refs: Vec<String> = task_glossary_refs(&task)
+            .iter()
+            .map(|r| r.to_string())
+            .collect();
+        assert_eq!(
+            refs,
+            [
+                "stellaris (game, priority 0)",
+                "dlc_overlord (dlc, priority 100)",
+                "family (mod_family, priority 200)",
+                "my_mod (mod, priority 300)",
+                "patch (mod, priority 400)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_glossary_origin_in_prompt() {
+        let glossary = from_json_file_content(r#"{"zro": {"1": "Zro", "2": "泽罗"}}"#)
+            .unwrap()
+            .with_origin(GlossaryOrigin {
+                name: "family".to_string(),
+                kind: GlossaryKind::Custom,
+                path: PathBuf::from("family.json"),
+                scope: GlossaryScope::ModFamily,
+                priority: 200,
+            });
+        assert_eq!(
+            glossary.to_csv("english", "simp_chinese", &["zro"]),
+            "english,simp_chinese,说明\nZro,泽罗,来源：family（mod_family）\n"
+        );
+    }
+
     /// 辅助函数：从字符串内容加载术语表（用于测试）
     fn from_json_file_content(content: &str) -> Result<Glossary> {
         let raw: serde_json::Value = serde_json::from_str(content).map_err(|e| {
@@ -481,6 +1252,9 @@ mod tests {
             ));
         }
 
-        Ok(Glossary { entries })
+        Ok(Glossary {
+            entries,
+            origins: HashMap::new(),
+        })
     }
 }
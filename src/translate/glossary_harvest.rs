@@ -0,0 +1,236 @@
+//! 术语采集模块
+//!
+//! 按键名对齐游戏原版的源语言与目标语言本地化文件，从中提取简短的名称类条目
+//! （建筑、岗位、资源、科技等）作为候选术语。
+
+use crate::error::Result;
+use crate::preprocess::{LocalisationEntry, find_localisation_files, read_entries};
+use crate::translate::glossary::{Glossary, GlossaryItem};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 这些后缀的键通常是描述、提示等长文本，而不是名称
+const EXCLUDED_KEY_SUFFIXES: [&str; 10] = [
+    "_desc", "_tooltip", "_tt", "_effect", "_text", "_title", "_flavor", "_intro", "_hint", "_msg",
+];
+
+/// 术语采集选项
+#[derive(Debug, Clone)]
+pub struct HarvestOptions {
+    pub source_lang: String,
+    pub target_lang: String,
+    /// 候选术语的最大单词数
+    pub max_words: usize,
+    /// 候选术语在全部源文本中出现的最少次数
+    pub min_frequency: usize,
+}
+
+impl Default for HarvestOptions {
+    fn default() -> Self {
+        Self {
+            source_lang: "english".to_string(),
+            target_lang: "simp_chinese".to_string(),
+            max_words: 4,
+            min_frequency: 3,
+        }
+    }
+}
+
+/// 采集结果
+#[derive(Debug, Default)]
+pub struct HarvestResult {
+    pub glossary: Glossary,
+    /// 两种语言中都存在的键数量
+    pub aligned: usize,
+    /// 通过名称筛选的候选条目数量
+    pub candidates: usize,
+    /// 因出现次数不足而被过滤的术语数量
+    pub below_frequency: usize,
+    /// 同一源术语存在多种译法的术语数量（已取最常见的译法）
+    pub ambiguous: usize,
+}
+
+/// 读取某语言目录下所有本地化文件的条目，同名键以先读到的为准
+pub fn read_language_tree(dir: &Path) -> Result<Vec<LocalisationEntry>> {
+    let mut entries = Vec::new();
+    for file in find_localisation_files(dir)? {
+        entries.extend(read_entries(&file)?);
+    }
+    Ok(entries)
+}
+
+/// 从对齐的源语言与目标语言条目中采集术语
+pub fn harvest_glossary(
+    source: &[LocalisationEntry],
+    target: &[LocalisationEntry],
+    options: &HarvestOptions,
+) -> Result<HarvestResult> {
+    let markup = Regex::new(r"§.|£[^£]*£|\$[^$]*\$|\[[^\]]*\]|\\n").unwrap();
+    let mut result = HarvestResult::default();
+
+    let mut target_values: HashMap<&str, &str> = HashMap::new();
+    for entry in target {
+        target_values.entry(&entry.key).or_insert(&entry.value);
+    }
+
+    // 统计源文本中所有不超过 max_words 的词组出现次数，用于频率过滤
+    let mut frequency: HashMap<String, usize> = HashMap::new();
+    for entry in source {
+        let words = split_words(&markup.replace_all(&entry.value, " "));
+        for n in 1..=options.max_words {
+            for window in words.windows(n) {
+                *frequency.entry(window.join(" ")).or_default() += 1;
+            }
+        }
+    }
+
+    // 源术语 -> (译法 -> 次数)
+    let mut translations: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    let mut seen = std::collections::HashSet::new();
+    for entry in source {
+        if !seen.insert(entry.key.as_str()) {
+            continue;
+        }
+        let Some(target_value) = target_values.get(entry.key.as_str()) else {
+            continue;
+        };
+        result.aligned += 1;
+        if !is_name_key(&entry.key)
+            || !is_name_value(&entry.value, options.max_words)
+            || !is_name_value(target_value, usize::MAX)
+        {
+            continue;
+        }
+        result.candidates += 1;
+        let term = split_words(&entry.value).join(" ");
+        *translations
+            .entry(term)
+            .or_default()
+            .entry(target_value.trim().to_string())
+            .or_default() += 1;
+    }
+
+    let mut terms: Vec<(String, HashMap<String, usize>)> = translations.into_iter().collect();
+    terms.sort_by(|a, b| a.0.cmp(&b.0));
+    for (term, renderings) in terms {
+        if frequency.get(&term).copied().unwrap_or(0) < options.min_frequency {
+            result.below_frequency += 1;
+            continue;
+        }
+        if renderings.len() > 1 {
+            result.ambiguous += 1;
+        }
+        let (rendering, _) = renderings
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+            .expect("term has at least one rendering");
+        let mut item = GlossaryItem::default();
+        item.set(&options.source_lang, Some(term.clone()))?;
+        item.set(&options.target_lang, Some(rendering))?;
+        result.glossary.insert(term, item)?;
+    }
+
+    Ok(result)
+}
+
+/// 键名是否可能是名称（而非描述、提示等长文本）
+pub(crate) fn is_name_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    !EXCLUDED_KEY_SUFFIXES
+        .iter()
+        .any(|suffix| key.ends_with(suffix))
+}
+
+/// 文本是否像一个名称：不含标记和句子标点，且单词数不超过上限
+pub(crate) fn is_name_value(value: &str, max_words: usize) -> bool {
+    let value = value.trim();
+    if value.is_empty()
+        || value.contains(['$', '£', '§', '[', ']', '\\'])
+        || value.contains(['.', '!', '?', ':', ';', '。', '！', '？', '：'])
+    {
+        return false;
+    }
+    let words = split_words(value);
+    !words.is_empty() && words.len() <= max_words
+}
+
+/// 将文本拆分为小写单词，保留单词内部的连字符和撇号
+pub(crate) fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '\''))
+        .map(|w| w.trim_matches(['-', '\'']))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocess::parse_entries;
+
+    fn harvest() -> HarvestResult {
+        let source = parse_entries(
+            r#"l_english:
+ building_lab:0 "Research Lab"
+ building_lab_desc:0 "A Research Lab produces research."
+ job_researcher:0 "Researcher"
+ job_researcher_plural:0 "Researchers"
+ rare_thing:0 "Unobtainium"
+ tip:0 "Each Research Lab employs a Researcher."
+ tip2:0 "Build a Research Lab. Researcher jobs."
+ ambiguous_a:0 "Researcher"
+"#,
+        );
+        let target = parse_entries(
+            r#"l_simp_chinese:
+ building_lab:0 "研究实验室"
+ building_lab_desc:0 "研究实验室产出科研。"
+ job_researcher:0 "研究员"
+ job_researcher_plural:0 "研究员"
+ rare_thing:0 "难得素"
+ ambiguous_a:0 "科研人员"
+"#,
+        );
+        let options = HarvestOptions {
+            min_frequency: 2,
+            ..Default::default()
+        };
+        harvest_glossary(&source, &target, &options).unwrap()
+    }
+
+    #[test]
+    fn test_harvest_name_entries() {
+        let result = harvest();
+        assert_eq!(
+            result
+                .glossary
+                .get("research lab")
+                .unwrap()
+                .get("simp_chinese"),
+            Some("研究实验室")
+        );
+    }
+
+    #[test]
+    fn test_harvest_picks_most_common_translation() {
+        let result = harvest();
+        assert_eq!(
+            result
+                .glossary
+                .get("researcher")
+                .unwrap()
+                .get("simp_chinese"),
+            Some("研究员")
+        );
+        assert_eq!(result.ambiguous, 1);
+    }
+
+    #[test]
+    fn test_harvest_skips_rare_terms() {
+        let result = harvest();
+        assert!(result.glossary.get("unobtainium").is_none());
+        assert!(result.glossary.get("researchers").is_none());
+        assert_eq!(result.below_frequency, 2);
+    }
+}
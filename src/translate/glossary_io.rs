@@ -0,0 +1,651 @@
+//! 术语表导入导出模块
+//!
+//! 在 Glossary 与 CSV/TSV 表格、TBX-Basic XML 之间转换，便于与共享表格和其他术语工具交换术语。
+//!
+//! 表格的第一行为表头，列名为语言代码（如 `english`、`simp_chinese`，也接受 `en`、`zh-CN`
+//! 等 BCP 47 代码），另有可选的 `key` 与 `note` 列。
+
+use crate::error::{Result, TranslateError, TranslationError};
+use crate::translate::glossary::{GLOSSARY_LANGUAGES, Glossary, GlossaryItem};
+use quick_xml::escape::{escape, unescape};
+use quick_xml::events::Event;
+use quick_xml::{Reader, XmlVersion};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+/// Paradox 语言名与 BCP 47 语言标签的对应关系，TBX 的 xml:lang 使用后者
+const LANGUAGE_TAGS: [(&str, &str); 10] = [
+    ("english", "en"),
+    ("simp_chinese", "zh-CN"),
+    ("spanish", "es"),
+    ("french", "fr"),
+    ("braz_por", "pt-BR"),
+    ("russian", "ru"),
+    ("german", "de"),
+    ("japanese", "ja"),
+    ("korean", "ko"),
+    ("polish", "pl"),
+];
+
+/// 与某个 Paradox 语言等价的其他常见 BCP 47 标签
+const LANGUAGE_ALIASES: [(&str, &str); 11] = [
+    ("english", "en-US"),
+    ("english", "en-GB"),
+    ("simp_chinese", "zh-Hans"),
+    ("simp_chinese", "zh-Hans-CN"),
+    ("spanish", "es-ES"),
+    ("french", "fr-FR"),
+    ("russian", "ru-RU"),
+    ("german", "de-DE"),
+    ("japanese", "ja-JP"),
+    ("korean", "ko-KR"),
+    ("polish", "pl-PL"),
+];
+
+/// 术语表交换格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlossaryFormat {
+    Csv,
+    Tsv,
+    Tbx,
+}
+
+impl GlossaryFormat {
+    /// 根据名称解析格式（csv、tsv、tbx）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(GlossaryFormat::Csv),
+            "tsv" | "tab" => Some(GlossaryFormat::Tsv),
+            "tbx" | "xml" => Some(GlossaryFormat::Tbx),
+            _ => None,
+        }
+    }
+
+    /// 根据文件扩展名推断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+    }
+}
+
+/// 导入时被拒绝的行（TBX 中为条目）
+#[derive(Debug, Clone)]
+pub struct RejectedRow {
+    /// 所在行号（从 1 开始）
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Display for RejectedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// 导入结果统计
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// 成功导入的条目数
+    pub imported: usize,
+    /// 无法导入的行
+    pub rejected: Vec<RejectedRow>,
+    /// 无法识别、已被忽略的列名或语言代码
+    pub ignored_languages: Vec<String>,
+}
+
+/// 将语言代码解析为术语表语言名
+///
+/// 接受 Paradox 语言名（`simp_chinese`）和 BCP 47 标签（`zh-CN`、`zh-Hans`、`pt`），不区分大小写；
+/// 无法识别的代码返回 None。
+/// 只有主语言子标签的代码（如 `pt`）按主语言匹配；带有地区或文字子标签、又不是已知标签的代码
+/// （如 `zh-TW`、`pt-PT`）可能是另一种语言变体，给出警告并返回 None，不当作同一种语言导入
+pub fn language_from_code(code: &str) -> Option<&'static str> {
+    let normalized = code.trim().to_lowercase().replace('_', "-");
+    if let Some(lang) = GLOSSARY_LANGUAGES
+        .iter()
+        .find(|lang| lang.replace('_', "-") == normalized)
+    {
+        return Some(lang);
+    }
+    if let Some((lang, _)) = LANGUAGE_TAGS
+        .iter()
+        .chain(LANGUAGE_ALIASES.iter())
+        .find(|(_, tag)| tag.to_lowercase() == normalized)
+    {
+        return Some(lang);
+    }
+    let mut subtags = normalized.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    let (lang, _) = LANGUAGE_TAGS
+        .iter()
+        .find(|(_, tag)| tag.split('-').next() == Some(primary))?;
+    if subtags.next().is_some() {
+        log::warn!(
+            "Skipping language '{}': it is a different variant than the supported '{}' ({})",
+            code.trim(),
+            lang,
+            language_tag(lang).unwrap_or_default()
+        );
+        return None;
+    }
+    Some(lang)
+}
+
+/// 获取术语表语言对应的 BCP 47 标签
+pub fn language_tag(lang: &str) -> Option<&'static str> {
+    LANGUAGE_TAGS
+        .iter()
+        .find(|(name, _)| *name == lang)
+        .map(|(_, tag)| *tag)
+}
+
+/// 从文本内容导入术语表
+///
+/// 单行或单个条目的错误不会中断导入，而是记录在 ImportReport 中；
+/// 只有整体格式错误（如缺少语言列、XML 无法解析）才会返回错误
+pub fn import_glossary(content: &str, format: GlossaryFormat) -> Result<(Glossary, ImportReport)> {
+    let content = content.trim_start_matches('\u{FEFF}');
+    match format {
+        GlossaryFormat::Csv => import_table(content, b','),
+        GlossaryFormat::Tsv => import_table(content, b'\t'),
+        GlossaryFormat::Tbx => import_tbx(content),
+    }
+}
+
+/// 将术语表导出为文本
+///
+/// langs 为空时导出术语表中出现过的所有语言
+pub fn export_glossary(
+    glossary: &Glossary,
+    format: GlossaryFormat,
+    langs: &[&str],
+) -> Result<String> {
+    let langs: Vec<&str> = if langs.is_empty() {
+        GLOSSARY_LANGUAGES
+            .iter()
+            .copied()
+            .filter(|lang| {
+                glossary
+                    .entries()
+                    .values()
+                    .any(|item| item.has_language(lang))
+            })
+            .collect()
+    } else {
+        for lang in langs {
+            if !GLOSSARY_LANGUAGES.contains(lang) {
+                return Err(format_error(format!(
+                    "Unsupported glossary language '{}'",
+                    lang
+                )));
+            }
+        }
+        langs.to_vec()
+    };
+
+    let mut keys: Vec<&String> = glossary.entries().keys().collect();
+    keys.sort();
+    let items: Vec<(&str, &GlossaryItem)> = keys
+        .into_iter()
+        .filter_map(|key| glossary.get(key).map(|item| (key.as_str(), item)))
+        .filter(|(_, item)| langs.iter().any(|lang| item.has_language(lang)))
+        .collect();
+
+    match format {
+        GlossaryFormat::Csv => export_table(&items, &langs, b','),
+        GlossaryFormat::Tsv => export_table(&items, &langs, b'\t'),
+        GlossaryFormat::Tbx => Ok(export_tbx(&items, &langs)),
+    }
+}
+
+/// 表格中各列的含义
+enum Column {
+    Key,
+    Note,
+    Language(&'static str),
+    Ignored,
+}
+
+fn import_table(content: &str, delimiter: u8) -> Result<(Glossary, ImportReport)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let mut report = ImportReport::default();
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format_error(format!("Failed to read header row: {}", e)))?
+        .clone();
+    let mut columns: Vec<Column> = Vec::new();
+    for header in headers.iter() {
+        columns.push(match header.trim().to_lowercase().as_str() {
+            "key" => Column::Key,
+            "note" | "notes" => Column::Note,
+            code => match language_from_code(code) {
+                Some(lang) => Column::Language(lang),
+                None => {
+                    report.ignored_languages.push(header.to_string());
+                    Column::Ignored
+                }
+            },
+        });
+    }
+    if !columns.iter().any(|c| matches!(c, Column::Language(_))) {
+        return Err(format_error(format!(
+            "No language column found in header: {}",
+            headers.iter().collect::<Vec<&str>>().join(", ")
+        )));
+    }
+
+    let mut builder = EntryBuilder::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line() as usize).unwrap_or(0);
+                report.rejected.push(RejectedRow {
+                    line,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line() as usize).unwrap_or(0);
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if record.len() > columns.len() {
+            report.rejected.push(RejectedRow {
+                line,
+                reason: format!(
+                    "expected at most {} fields, found {}",
+                    columns.len(),
+                    record.len()
+                ),
+            });
+            continue;
+        }
+
+        let mut entry = PendingEntry {
+            line,
+            ..Default::default()
+        };
+        for (column, field) in columns.iter().zip(record.iter()) {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+            match column {
+                Column::Key => entry.key = Some(field.to_string()),
+                Column::Note => entry.note = Some(field.to_string()),
+                Column::Language(lang) => entry.terms.push((lang, field.to_string())),
+                Column::Ignored => {}
+            }
+        }
+        builder.push(entry, &mut report);
+    }
+
+    Ok((builder.glossary, report))
+}
+
+fn export_table(items: &[(&str, &GlossaryItem)], langs: &[&str], delimiter: u8) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    let mut header = vec!["key"];
+    header.extend(langs);
+    header.push("note");
+    writer
+        .write_record(&header)
+        .map_err(|e| format_error(e.to_string()))?;
+    for (key, item) in items {
+        let mut record = vec![*key];
+        record.extend(langs.iter().map(|lang| item.get(lang).unwrap_or_default()));
+        record.push(item.note.as_deref().unwrap_or_default());
+        writer
+            .write_record(&record)
+            .map_err(|e| format_error(e.to_string()))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format_error(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| format_error(e.to_string()))
+}
+
+/// 导出为 TBX-Basic（TBX v2 的 martif 结构，兼容性最好）
+fn export_tbx(items: &[(&str, &GlossaryItem)], langs: &[&str]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<martif type=\"TBX-Basic\" xml:lang=\"en\">\n");
+    xml.push_str("  <martifHeader>\n");
+    xml.push_str("    <fileDesc>\n");
+    xml.push_str("      <sourceDesc>\n");
+    xml.push_str("        <p>Exported by paradox-mod-translator</p>\n");
+    xml.push_str("      </sourceDesc>\n");
+    xml.push_str("    </fileDesc>\n");
+    xml.push_str("  </martifHeader>\n");
+    xml.push_str("  <text>\n");
+    xml.push_str("    <body>\n");
+    for (i, (_, item)) in items.iter().enumerate() {
+        xml.push_str(&format!("      <termEntry id=\"term-{}\">\n", i + 1));
+        if let Some(note) = &item.note {
+            xml.push_str(&format!("        <note>{}</note>\n", escape(note.as_str())));
+        }
+        for lang in langs {
+            let (Some(term), Some(tag)) = (item.get(lang), language_tag(lang)) else {
+                continue;
+            };
+            xml.push_str(&format!("        <langSet xml:lang=\"{}\">\n", tag));
+            xml.push_str("          <tig>\n");
+            xml.push_str(&format!("            <term>{}</term>\n", escape(term)));
+            xml.push_str("          </tig>\n");
+            xml.push_str("        </langSet>\n");
+        }
+        xml.push_str("      </termEntry>\n");
+    }
+    xml.push_str("    </body>\n");
+    xml.push_str("  </text>\n");
+    xml.push_str("</martif>\n");
+    xml
+}
+
+/// 导入 TBX，同时支持 TBX v2（termEntry/langSet/tig）与 TBX v3（conceptEntry/langSec/termSec）
+///
+/// TBX 条目的 id 不一定与术语表 key 相同，因此 key 由英文术语的小写形式生成，
+/// 没有英文术语时才使用条目 id
+fn import_tbx(content: &str) -> Result<(Glossary, ImportReport)> {
+    // 不启用 trim_text：实体引用会把文本拆成多段，逐段去除空白会丢失术语中间的空格
+    let mut reader = Reader::from_str(content);
+    let mut report = ImportReport::default();
+    let mut builder = EntryBuilder::default();
+
+    let mut entry: Option<PendingEntry> = None;
+    let mut lang: Option<&'static str> = None;
+    let mut in_lang = false;
+    // 正在读取的元素：term 或条目级 note
+    let mut capture: Option<&'static str> = None;
+    let mut text = String::new();
+
+    loop {
+        let position = reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(|e| {
+            format_error(format!(
+                "Invalid TBX at line {}: {}",
+                line_at(content, position),
+                e
+            ))
+        })?;
+        match event {
+            Event::Start(e) => match e.local_name().as_ref() {
+                "termEntry" | "conceptEntry" => {
+                    let id = e
+                        .try_get_attribute("id")
+                        .ok()
+                        .flatten()
+                        .and_then(|a| a.normalized_value(XmlVersion::Implicit1_0).ok())
+                        .map(|v| v.to_lowercase());
+                    entry = Some(PendingEntry {
+                        line: line_at(content, position),
+                        fallback_key: id,
+                        ..Default::default()
+                    });
+                }
+                "langSet" | "langSec" => {
+                    in_lang = true;
+                    let code = e
+                        .try_get_attribute("xml:lang")
+                        .ok()
+                        .flatten()
+                        .and_then(|a| a.normalized_value(XmlVersion::Implicit1_0).ok())
+                        .map(|v| v.to_string())
+                        .unwrap_or_default();
+                    lang = language_from_code(&code);
+                    if lang.is_none() && !report.ignored_languages.contains(&code) {
+                        report.ignored_languages.push(code);
+                    }
+                }
+                "term" if entry.is_some() && lang.is_some() => {
+                    capture = Some("term");
+                    text.clear();
+                }
+                "note" if entry.is_some() && !in_lang => {
+                    capture = Some("note");
+                    text.clear();
+                }
+                _ => {}
+            },
+            Event::Text(t) if capture.is_some() => text.push_str(&t.xml10_content()),
+            Event::CData(t) if capture.is_some() => text.push_str(&t.into_inner()),
+            Event::GeneralRef(r) if capture.is_some() => {
+                let raw = format!("&{};", &*r);
+                match unescape(&raw) {
+                    Ok(resolved) => text.push_str(&resolved),
+                    Err(_) => text.push_str(&raw),
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                "term" | "note" => {
+                    if let (Some(kind), Some(pending)) = (capture.take(), entry.as_mut()) {
+                        let value = text.trim().to_string();
+                        if value.is_empty() {
+                            continue;
+                        }
+                        match (kind, lang) {
+                            // 同一语言只取第一个术语
+                            ("term", Some(lang))
+                                if !pending.terms.iter().any(|(l, _)| *l == lang) =>
+                            {
+                                pending.terms.push((lang, value));
+                            }
+                            ("note", _) => pending.note = Some(value),
+                            _ => {}
+                        }
+                    }
+                }
+                "langSet" | "langSec" => {
+                    in_lang = false;
+                    lang = None;
+                }
+                "termEntry" | "conceptEntry" => {
+                    if let Some(pending) = entry.take() {
+                        builder.push(pending, &mut report);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok((builder.glossary, report))
+}
+
+/// 尚未校验的导入条目
+#[derive(Default)]
+struct PendingEntry {
+    line: usize,
+    key: Option<String>,
+    /// 既没有 key 也没有英文术语时使用的 key
+    fallback_key: Option<String>,
+    note: Option<String>,
+    terms: Vec<(&'static str, String)>,
+}
+
+/// 逐条校验导入条目并构建术语表
+#[derive(Default)]
+struct EntryBuilder {
+    glossary: Glossary,
+    /// key -> 首次定义所在行
+    seen: HashMap<String, usize>,
+}
+
+impl EntryBuilder {
+    fn push(&mut self, entry: PendingEntry, report: &mut ImportReport) {
+        let reject = |report: &mut ImportReport, reason: String| {
+            report.rejected.push(RejectedRow {
+                line: entry.line,
+                reason,
+            })
+        };
+        if entry.terms.is_empty() {
+            reject(report, "no term in any supported language".to_string());
+            return;
+        }
+        let english = entry
+            .terms
+            .iter()
+            .find(|(lang, _)| *lang == "english")
+            .map(|(_, term)| term.to_lowercase());
+        let Some(key) = entry.key.clone().or(english).or(entry.fallback_key.clone()) else {
+            reject(report, "missing key and english term".to_string());
+            return;
+        };
+        if let Some(first) = self.seen.get(&key) {
+            reject(
+                report,
+                format!("duplicate key '{}', first defined on line {}", key, first),
+            );
+            return;
+        }
+
+        let mut item = GlossaryItem {
+            note: entry.note.clone(),
+            ..Default::default()
+        };
+        for (lang, term) in &entry.terms {
+            if let Err(e) = item.set(lang, Some(term.clone())) {
+                reject(report, e.to_string());
+                return;
+            }
+        }
+        match self.glossary.insert(key.clone(), item) {
+            Ok(_) => {
+                self.seen.insert(key, entry.line);
+                report.imported += 1;
+            }
+            Err(e) => reject(report, e.to_string()),
+        }
+    }
+}
+
+/// 计算字节偏移所在的行号（从 1 开始）
+fn line_at(content: &str, position: usize) -> usize {
+    let end = position.min(content.len());
+    content.as_bytes()[..end]
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count()
+        + 1
+}
+
+/// 术语表文件内容无法解析或格式不符合要求
+fn format_error(message: String) -> TranslationError {
+    TranslationError::Translate(TranslateError::GlossaryError(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Glossary {
+        let mut glossary = Glossary::default();
+        let mut energy = GlossaryItem {
+            note: Some("the resource, not the ethic".to_string()),
+            ..Default::default()
+        };
+        energy.set("english", Some("energy".to_string())).unwrap();
+        energy
+            .set("simp_chinese", Some("能量".to_string()))
+            .unwrap();
+        energy
+            .set("french", Some("énergie, \"crédits\"".to_string()))
+            .unwrap();
+        glossary.insert("energy".to_string(), energy).unwrap();
+        let mut minerals = GlossaryItem::default();
+        minerals
+            .set("english", Some("minerals".to_string()))
+            .unwrap();
+        minerals
+            .set("simp_chinese", Some("矿物".to_string()))
+            .unwrap();
+        glossary.insert("minerals".to_string(), minerals).unwrap();
+        glossary
+    }
+
+    fn assert_same(a: &Glossary, b: &Glossary) {
+        assert_eq!(a.len(), b.len());
+        for (key, item) in a.entries() {
+            let other = b.get(key).unwrap();
+            assert_eq!(item.all_terms(), other.all_terms());
+            assert_eq!(item.note, other.note);
+        }
+    }
+
+    #[test]
+    fn test_table_roundtrip() {
+        let glossary = sample();
+        for format in [GlossaryFormat::Csv, GlossaryFormat::Tsv] {
+            let text = export_glossary(&glossary, format, &[]).unwrap();
+            let (imported, report) = import_glossary(&text, format).unwrap();
+            assert!(report.rejected.is_empty(), "{:?}", report.rejected);
+            assert_same(&glossary, &imported);
+        }
+    }
+
+    #[test]
+    fn test_tbx_roundtrip() {
+        let glossary = sample();
+        let text = export_glossary(&glossary, GlossaryFormat::Tbx, &[]).unwrap();
+        assert!(text.contains("<langSet xml:lang=\"zh-CN\">"));
+        let (imported, report) = import_glossary(&text, GlossaryFormat::Tbx).unwrap();
+        assert!(report.rejected.is_empty(), "{:?}", report.rejected);
+        assert_same(&glossary, &imported);
+    }
+
+    const BAD_CSV: &str = "en,zh-Hans,klingon\n\
+                           energy,能量,x\n\
+                           ,,\n\
+                           ,矿物,\n\
+                           Energy,能源,\n";
+
+    #[test]
+    fn test_csv_import_reports_bad_rows() {
+        let (glossary, report) = import_glossary(BAD_CSV, GlossaryFormat::Csv).unwrap();
+        assert_eq!(glossary.len(), 1);
+        assert_eq!(report.imported, 1);
+        let lines: Vec<usize> = report.rejected.iter().map(|r| r.line).collect();
+        assert_eq!(lines, vec![4, 5]);
+        assert!(report.rejected[1].reason.contains("duplicate key"));
+    }
+
+    #[test]
+    fn test_csv_import_reports_unknown_languages() {
+        let (_, report) = import_glossary(BAD_CSV, GlossaryFormat::Csv).unwrap();
+        assert_eq!(report.ignored_languages, vec!["klingon".to_string()]);
+    }
+
+    #[test]
+    fn test_language_from_code() {
+        let lang = language_from_code;
+        assert_eq!(lang("simp_chinese"), Some("simp_chinese"));
+        assert_eq!(lang("zh-Hans"), Some("simp_chinese"));
+        assert_eq!(lang("PT"), Some("braz_por"));
+        assert_eq!(lang("en-US"), Some("english"));
+        assert_eq!(lang("tlh"), None);
+    }
+
+    #[test]
+    fn test_language_variants_are_skipped() {
+        for code in ["zh-TW", "zh-Hant", "pt-PT"] {
+            assert_eq!(language_from_code(code), None, "{}", code);
+        }
+        let csv = "en,zh-Hans,zh-TW\nenergy,能量,能量\n";
+        let (glossary, report) = import_glossary(csv, GlossaryFormat::Csv).unwrap();
+        assert_eq!(glossary.len(), 1);
+        assert_eq!(report.ignored_languages, vec!["zh-TW".to_string()]);
+    }
+}
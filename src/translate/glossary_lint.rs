@@ -0,0 +1,443 @@
+//! 术语表冲突检测模块
+//!
+//! 合并多个术语表时检测冲突（同一 key 的译法不同、同一源术语出现在多个 key 下、
+//! 源术语仅大小写不同），并按照任务配置的策略处理；同时提供术语表文件的逐条检查。
+
+use crate::config::GlossaryConflictPolicy;
+use crate::error::{Result, TranslateError, TranslationError};
+use crate::translate::glossary::{GLOSSARY_LANGUAGES, Glossary, GlossaryItem, validate_entry};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// 术语表冲突
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlossaryConflict {
+    /// 同一 key 在不同术语表中的术语不同
+    KeyOverride {
+        key: String,
+        lang: String,
+        previous: String,
+        previous_origin: String,
+        current: String,
+        current_origin: String,
+    },
+    /// 同一源术语出现在多个 key 下，且这些 key 的译法不同
+    DuplicateTerm {
+        lang: String,
+        term: String,
+        keys: Vec<String>,
+        /// 最终保留的 key
+        kept: String,
+    },
+    /// 多个 key 下的源术语仅大小写不同
+    CaseVariant {
+        lang: String,
+        /// (key, 术语)
+        terms: Vec<(String, String)>,
+        /// 最终保留的 key
+        kept: String,
+    },
+}
+
+impl std::fmt::Display for GlossaryConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlossaryConflict::KeyOverride {
+                key,
+                lang,
+                previous,
+                previous_origin,
+                current,
+                current_origin,
+            } => write!(
+                f,
+                "Key '{}' has {} term '{}' in {} but '{}' in {}",
+                key, lang, previous, previous_origin, current, current_origin
+            ),
+            GlossaryConflict::DuplicateTerm {
+                lang,
+                term,
+                keys,
+                kept,
+            } => write!(
+                f,
+                "{} term '{}' is defined under multiple keys: {} (preferred: '{}')",
+                lang,
+                term,
+                keys.join(", "),
+                kept
+            ),
+            GlossaryConflict::CaseVariant { lang, terms, kept } => {
+                let terms: Vec<String> = terms
+                    .iter()
+                    .map(|(key, term)| format!("'{}' (key '{}')", term, key))
+                    .collect();
+                write!(
+                    f,
+                    "{} terms differ only in case: {} (preferred: '{}')",
+                    lang,
+                    terms.join(", "),
+                    kept
+                )
+            }
+        }
+    }
+}
+
+/// 按顺序合并术语表（后者优先），检测冲突并按策略处理
+///
+/// + custom_wins：后加载的条目覆盖先加载的；同一源术语的多个 key 只保留优先级最高的一个
+/// + alternatives：同上，但被覆盖或被丢弃的译法保留为其他可接受的译法
+/// + fail：存在任何冲突时返回错误
+///
+/// 返回合并后的术语表和检测到的所有冲突
+pub fn merge_glossaries_with_policy(
+    glossaries: &[Glossary],
+    source_lang: &str,
+    policy: GlossaryConflictPolicy,
+) -> Result<(Glossary, Vec<GlossaryConflict>)> {
+    let mut merged = Glossary::default();
+    let mut priority: HashMap<String, usize> = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for (index, glossary) in glossaries.iter().enumerate() {
+        let mut keys: Vec<&String> = glossary.entries().keys().collect();
+        keys.sort();
+        for key in keys {
+            let mut item = glossary.entries()[key].clone();
+            if let Some(existing) = merged.get(key) {
+                for lang in GLOSSARY_LANGUAGES {
+                    if let (Some(previous), Some(current)) = (existing.get(lang), item.get(lang))
+                        && previous != current
+                    {
+                        conflicts.push(GlossaryConflict::KeyOverride {
+                            key: key.clone(),
+                            lang: lang.to_string(),
+                            previous: previous.to_string(),
+                            previous_origin: describe_origin(&merged, key),
+                            current: current.to_string(),
+                            current_origin: describe_origin(glossary, key),
+                        });
+                        if policy == GlossaryConflictPolicy::Alternatives {
+                            item.add_alternative(lang, previous);
+                        }
+                    }
+                }
+            }
+            let origin = glossary.origin(key).cloned();
+            merged.insert_with_origin(key.clone(), item, origin);
+            priority.insert(key.clone(), index);
+        }
+    }
+
+    // 按源术语（不区分大小写）分组，查找重复和大小写变体
+    let mut folded: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (key, item) in merged.entries() {
+        if let Some(term) = item.get(source_lang) {
+            folded
+                .entry(term.to_lowercase())
+                .or_default()
+                .push(key.clone());
+        }
+    }
+    let groups: Vec<(String, Vec<String>)> = folded
+        .into_iter()
+        .flat_map(|(lower, keys)| {
+            split_case_sensitive(&merged, keys, source_lang)
+                .into_iter()
+                .map(move |keys| (lower.clone(), keys))
+        })
+        .collect();
+    for (lower, mut keys) in groups {
+        if keys.len() < 2 {
+            continue;
+        }
+        keys.sort();
+        // 优先级：所在术语表靠后 > key 与源术语一致 > key 排序靠前
+        let kept = keys
+            .iter()
+            .max_by(|a, b| {
+                priority[*a]
+                    .cmp(&priority[*b])
+                    .then_with(|| (*a == &lower).cmp(&(*b == &lower)))
+                    .then_with(|| b.cmp(a))
+            })
+            .cloned()
+            .expect("group has at least two keys");
+        let terms: Vec<(String, String)> = keys
+            .iter()
+            .map(|key| {
+                let term = merged.get(key).and_then(|item| item.get(source_lang));
+                (key.clone(), term.unwrap_or_default().to_string())
+            })
+            .collect();
+        // 译法也相同的重复条目（包括只有大小写不同的）只是冗余，不算冲突，仍只保留一个
+        if translations_diverge(&merged, &keys, source_lang) {
+            if terms.iter().all(|(_, term)| *term == terms[0].1) {
+                conflicts.push(GlossaryConflict::DuplicateTerm {
+                    lang: source_lang.to_string(),
+                    term: terms[0].1.clone(),
+                    keys: keys.clone(),
+                    kept: kept.clone(),
+                });
+            } else {
+                conflicts.push(GlossaryConflict::CaseVariant {
+                    lang: source_lang.to_string(),
+                    terms,
+                    kept: kept.clone(),
+                });
+            }
+        }
+
+        if policy == GlossaryConflictPolicy::Fail {
+            continue;
+        }
+        let dropped: Vec<GlossaryItem> = keys
+            .iter()
+            .filter(|key| **key != kept)
+            .filter_map(|key| merged.remove(key))
+            .collect();
+        if policy == GlossaryConflictPolicy::Alternatives
+            && let Some(winner) = merged.get_mut(&kept)
+        {
+            for item in &dropped {
+                for (lang, term) in item.all_terms() {
+                    if lang == source_lang {
+                        continue;
+                    }
+                    if winner.has_language(lang) {
+                        winner.add_alternative(lang, term);
+                    } else {
+                        winner.set(lang, Some(term.to_string()))?;
+                    }
+                }
+            }
+        }
+    }
+
+    if policy == GlossaryConflictPolicy::Fail && !conflicts.is_empty() {
+        let details: Vec<String> = conflicts.iter().map(|c| format!("  {}", c)).collect();
+        return Err(TranslationError::Translate(TranslateError::GlossaryError(
+            format!(
+                "Found {} glossary conflicts:\n{}",
+                conflicts.len(),
+                details.join("\n")
+            ),
+        )));
+    }
+    Ok((merged, conflicts))
+}
+
+/// 逐条检查术语表文件，返回无法解析或不合法的条目说明
+pub fn lint_glossary_file(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)?;
+    let raw: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+        TranslationError::Translate(TranslateError::GlossaryError(format!(
+            "{}: {}",
+            path.display(),
+            e
+        )))
+    })?;
+    let serde_json::Value::Object(obj) = raw else {
+        return Ok(vec!["glossary file must be a JSON object".to_string()]);
+    };
+    let mut issues = Vec::new();
+    for (key, value) in obj {
+        match serde_json::from_value::<GlossaryItem>(value) {
+            Ok(item) => {
+                if let Err(e) = validate_entry(&key, &item) {
+                    issues.push(e.to_string());
+                }
+            }
+            Err(e) => issues.push(format!("Glossary entry '{}' is invalid: {}", key, e)),
+        }
+    }
+    Ok(issues)
+}
+
+/// 将仅大小写不同的一组 key 按是否区分大小写拆分
+///
+/// 区分大小写的条目只与源术语完全相同的条目归为一组，其余条目仍按不区分大小写归为一组
+fn split_case_sensitive(
+    glossary: &Glossary,
+    keys: Vec<String>,
+    source_lang: &str,
+) -> Vec<Vec<String>> {
+    let mut exact: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for key in keys {
+        let term = glossary
+            .get(&key)
+            .and_then(|item| item.get(source_lang))
+            .unwrap_or_default();
+        exact.entry(term).or_default().push(key);
+    }
+    let mut groups = Vec::new();
+    let mut insensitive = Vec::new();
+    for (_, keys) in exact {
+        if keys
+            .iter()
+            .any(|key| glossary.get(key).is_some_and(|item| item.case_sensitive))
+        {
+            groups.push(keys);
+        } else {
+            insensitive.extend(keys);
+        }
+    }
+    groups.push(insensitive);
+    groups
+}
+
+/// 这些 key 在源语言以外的某种语言中是否有不同的译法
+fn translations_diverge(glossary: &Glossary, keys: &[String], source_lang: &str) -> bool {
+    GLOSSARY_LANGUAGES
+        .iter()
+        .filter(|lang| **lang != source_lang)
+        .any(|lang| {
+            let terms: HashSet<&str> = keys
+                .iter()
+                .filter_map(|key| glossary.get(key)?.get(lang))
+                .collect();
+            terms.len() > 1
+        })
+}
+
+fn describe_origin(glossary: &Glossary, key: &str) -> String {
+    glossary
+        .origin(key)
+        .map(|origin| origin.to_string())
+        .unwrap_or_else(|| "unknown glossary".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glossary(items: &[(&str, &str, &str)]) -> Glossary {
+        let mut glossary = Glossary::default();
+        for (key, en, zh) in items {
+            let mut item = GlossaryItem::default();
+            item.set("english", Some(en.to_string())).unwrap();
+            item.set("simp_chinese", Some(zh.to_string())).unwrap();
+            glossary.insert(key.to_string(), item).unwrap();
+        }
+        glossary
+    }
+
+    fn layers() -> Vec<Glossary> {
+        vec![
+            glossary(&[
+                ("energy", "energy", "能量"),
+                ("energy_credits", "energy", "能量币"),
+                ("alloys", "alloys", "合金"),
+            ]),
+            glossary(&[("alloys", "alloys", "合金材料"), ("fleet", "Fleet", "舰队")]),
+            glossary(&[("fleet_navy", "fleet", "海军")]),
+        ]
+    }
+
+    fn merge(policy: GlossaryConflictPolicy) -> (Glossary, Vec<GlossaryConflict>) {
+        merge_glossaries_with_policy(&layers(), "english", policy).unwrap()
+    }
+
+    #[test]
+    fn test_merge_later_layer_overrides_key() {
+        let (merged, conflicts) = merge(GlossaryConflictPolicy::CustomWins);
+        assert!(matches!(
+            &conflicts[0],
+            GlossaryConflict::KeyOverride { key, previous, current, .. }
+                if key == "alloys" && previous == "合金" && current == "合金材料"
+        ));
+        let alloys = merged.get("alloys").unwrap();
+        assert_eq!(alloys.get("simp_chinese"), Some("合金材料"));
+        assert!(alloys.alternatives("simp_chinese").is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_first_duplicate_term() {
+        let (merged, conflicts) = merge(GlossaryConflictPolicy::CustomWins);
+        assert!(matches!(
+            &conflicts[1],
+            GlossaryConflict::DuplicateTerm { keys, kept, .. }
+                if keys == &["energy", "energy_credits"] && kept == "energy"
+        ));
+        assert!(merged.get("energy_credits").is_none());
+    }
+
+    #[test]
+    fn test_merge_keeps_latest_case_variant() {
+        let (merged, conflicts) = merge(GlossaryConflictPolicy::CustomWins);
+        assert_eq!(conflicts.len(), 3);
+        assert!(matches!(
+            &conflicts[2],
+            GlossaryConflict::CaseVariant { kept, .. } if kept == "fleet_navy"
+        ));
+        assert!(merged.get("fleet").is_none());
+        assert_eq!(merged.len(), 3);
+    }
+
+    #[test]
+    fn test_merge_conflicts_as_alternatives() {
+        let (merged, _) = merge(GlossaryConflictPolicy::Alternatives);
+        assert_eq!(
+            merged.get("alloys").unwrap().alternatives("simp_chinese"),
+            ["合金"]
+        );
+        assert_eq!(
+            merged.get("energy").unwrap().alternatives("simp_chinese"),
+            ["能量币"]
+        );
+    }
+
+    #[test]
+    fn test_alternatives_in_prompt_csv() {
+        let (merged, _) = merge(GlossaryConflictPolicy::Alternatives);
+        let csv = merged.to_csv("english", "simp_chinese", &["energy"]);
+        assert_eq!(csv, "english,simp_chinese\nenergy,能量 / 能量币\n");
+    }
+
+    #[test]
+    fn test_merge_fails_on_conflict() {
+        let result =
+            merge_glossaries_with_policy(&layers(), "english", GlossaryConflictPolicy::Fail);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_merge_ignores_duplicates_with_same_translation() {
+        let layers = vec![
+            glossary(&[("energy", "energy", "能量")]),
+            glossary(&[("energy_resource", "energy", "能量")]),
+        ];
+        let (merged, conflicts) =
+            merge_glossaries_with_policy(&layers, "english", GlossaryConflictPolicy::Fail).unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_ignores_case_variants_with_same_translation() {
+        let layers = vec![
+            glossary(&[("alloys", "Alloys", "合金")]),
+            glossary(&[("alloys_lower", "alloys", "合金")]),
+        ];
+        let (_, conflicts) =
+            merge_glossaries_with_policy(&layers, "english", GlossaryConflictPolicy::Fail).unwrap();
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_case_sensitive_variants() {
+        let mut layers = vec![glossary(&[("us", "us", "我们")]), glossary(&[])];
+        let mut item = GlossaryItem::default();
+        item.set("english", Some("US".to_string())).unwrap();
+        item.set("simp_chinese", Some("美国".to_string())).unwrap();
+        item.case_sensitive = true;
+        layers[1].insert("us_country".to_string(), item).unwrap();
+
+        let (merged, conflicts) =
+            merge_glossaries_with_policy(&layers, "english", GlossaryConflictPolicy::CustomWins)
+                .unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.len(), 2);
+    }
+}
@@ -0,0 +1,180 @@
+//! 键索引模块
+//!
+//! 汇总任务中所有源文件的条目，用于在提示词中展开文本里以 `$key$` 引用的其他条目，
+//! 使大模型了解引用处实际显示的内容及其已有译文。
+
+use crate::error::Result;
+use crate::preprocess::{LocalisationEntry, read_entries};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// 键索引
+#[derive(Debug)]
+pub struct KeyIndex {
+    /// 键名 -> 源文本
+    source: HashMap<String, String>,
+    /// 目标语言 -> (键名 -> 译文)
+    translations: HashMap<String, HashMap<String, String>>,
+    /// 源文本中的引用：(引用方键名, 被引用键名)
+    references: Vec<(String, String)>,
+    reference_pattern: Regex,
+}
+
+impl Default for KeyIndex {
+    fn default() -> Self {
+        Self {
+            source: HashMap::new(),
+            translations: HashMap::new(),
+            references: Vec::new(),
+            reference_pattern: Regex::new(r"\$([A-Za-z0-9_.\-]+)(?:\|[^$]*)?\$").unwrap(),
+        }
+    }
+}
+
+impl KeyIndex {
+    /// 从源文件构建索引，同名键以先读到的为准
+    pub fn from_files(files: &[PathBuf]) -> Result<Self> {
+        let mut index = Self::default();
+        for file in files {
+            index.insert_source(read_entries(file)?);
+        }
+        Ok(index)
+    }
+
+    /// 添加源语言条目
+    pub fn insert_source(&mut self, entries: impl IntoIterator<Item = LocalisationEntry>) {
+        for entry in entries {
+            for key in self.find_references(&entry.value) {
+                self.references.push((entry.key.clone(), key));
+            }
+            self.source.entry(entry.key).or_insert(entry.value);
+        }
+    }
+
+    /// 记录某目标语言的译文，覆盖已有的译文
+    pub fn record_translations(
+        &mut self,
+        target_lang: &str,
+        entries: impl IntoIterator<Item = LocalisationEntry>,
+    ) {
+        let translations = self
+            .translations
+            .entry(target_lang.to_string())
+            .or_default();
+        for entry in entries {
+            translations.insert(entry.key, entry.value);
+        }
+    }
+
+    /// 读取已有的译文文件，返回读取到的条目数
+    pub fn load_translations(&mut self, target_lang: &str, files: &[PathBuf]) -> Result<usize> {
+        let mut count = 0;
+        for file in files {
+            let entries = read_entries(file)?;
+            count += entries.len();
+            self.record_translations(target_lang, entries);
+        }
+        Ok(count)
+    }
+
+    /// 源文本中被引用、但索引中不存在的键，返回 (引用方键名, 被引用键名)
+    ///
+    /// 全大写的名称（如 `$VALUE$`、`$AMOUNT$`）是游戏运行时填入的脚本变量，不是本地化键，不计入
+    pub fn missing_references(&self) -> Vec<(&str, &str)> {
+        let mut seen = HashSet::new();
+        self.references
+            .iter()
+            .filter(|(_, key)| !self.source.contains_key(key) && !is_scripted_variable(key))
+            .filter(|reference| seen.insert(*reference))
+            .map(|(from, key)| (from.as_str(), key.as_str()))
+            .collect()
+    }
+
+    /// 查找文本中引用的键名（去重，保持出现顺序）
+    pub fn find_references(&self, text: &str) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for caps in self.reference_pattern.captures_iter(text) {
+            let key = &caps[1];
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+            }
+        }
+        keys
+    }
+
+    /// 将文本中引用的、索引中存在的键格式化为提示词内容，没有引用时返回空字符串
+    ///
+    /// 每行格式为 `$key$ = "源文本" -> "译文"`，尚无译文时标注（尚未翻译）
+    pub fn format_references(&self, text: &str, target_lang: &str) -> String {
+        let translations = self.translations.get(target_lang);
+        let mut lines = Vec::new();
+        for key in self.find_references(text) {
+            let Some(source) = self.source.get(&key) else {
+                continue;
+            };
+            let line = match translations.and_then(|t| t.get(&key)) {
+                Some(translated) => format!("${}$ = \"{}\" -> \"{}\"", key, source, translated),
+                None => format!("${}$ = \"{}\"（尚未翻译）", key, source),
+            };
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+/// 名称中没有小写字母，按惯例为脚本变量而不是本地化键
+fn is_scripted_variable(name: &str) -> bool {
+    !name.chars().any(|c| c.is_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocess::parse_entries;
+
+    const DESC: &str = " desc:0 \"§H$tech_micro$§!\\n$zone_tt$ $vanilla_key|Y$ $tech_micro$\"";
+
+    fn index() -> KeyIndex {
+        let mut index = KeyIndex::default();
+        index.insert_source(parse_entries(&format!(
+            " tech_micro:0 \"Micro Terraforming\"\n zone_tt:0 \"Unlocks a zone\"\n{}\n",
+            DESC
+        )));
+        index.record_translations(
+            "simp_chinese",
+            parse_entries("l_simp_chinese:\n tech_micro:0 \"微型地形改造\"\n"),
+        );
+        index
+    }
+
+    #[test]
+    fn test_find_references_once_in_order() {
+        assert_eq!(
+            index().find_references(DESC),
+            vec!["tech_micro", "zone_tt", "vanilla_key"]
+        );
+    }
+
+    #[test]
+    fn test_format_references_with_translations() {
+        assert_eq!(
+            index().format_references(DESC, "simp_chinese"),
+            "$tech_micro$ = \"Micro Terraforming\" -> \"微型地形改造\"\n$zone_tt$ = \"Unlocks a zone\"（尚未翻译）"
+        );
+    }
+
+    #[test]
+    fn test_missing_references() {
+        assert_eq!(index().missing_references(), vec![("desc", "vanilla_key")]);
+    }
+
+    #[test]
+    fn test_scripted_variables_are_not_missing_keys() {
+        let mut index = KeyIndex::default();
+        index.insert_source(parse_entries(
+            " gain:0 \"Gain $VALUE|+0$ $AMOUNT$ $ENERGY_COST$ and $vanilla_key$\"\n",
+        ));
+        assert_eq!(index.missing_references(), vec![("gain", "vanilla_key")]);
+    }
+}
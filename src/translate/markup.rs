@@ -0,0 +1,476 @@
+//! Paradox 本地化标记解析模块
+//!
+//! 将条目的文本切分为普通文本与标记：颜色 `§X…§!`、参数 `$name|arg$`、图标 `£icon|frame£`、
+//! 可嵌套的命令 `[…]`、概念命令 `['concept' text]` 以及转义序列；
+//! 各游戏支持的进阶标记（CK3/Vic3 的 `#format … #!` 与 `@icon!`）由 [`MarkupSyntax`] 开启。
+//! 验证器按标记比较译文与原文的结构，而不是用相互独立的正则表达式匹配。
+
+use std::fmt::Display;
+
+/// 文本中的一个片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupToken<'a> {
+    /// 普通文本
+    Text(&'a str),
+    /// 转义序列，如 `\n`、`\"`
+    Escape(&'a str),
+    /// 颜色开始，如 `§Y`
+    ColorStart(&'a str),
+    /// 颜色结束 `§!`
+    ColorEnd(&'a str),
+    /// 参数，如 `$name$`、`$VALUE|Y0$`
+    Variable(&'a str),
+    /// 图标，如 `£energy£`、`£trigger_yes|2£`
+    Icon(&'a str),
+    /// 命令，如 `[Root.GetName]`，可以包含嵌套的 `[…]`
+    Command(&'a str),
+    /// 概念命令 `['concept' text]`，其中的说明文本需要翻译
+    Concept {
+        raw: &'a str,
+        key: &'a str,
+        text: &'a str,
+    },
+    /// 没有闭合的 `[`
+    UnclosedCommand(&'a str),
+    /// 文本格式开始，如 `#bold`、`#high;tooltippable`（CK3/Vic3）
+    FormatStart(&'a str),
+    /// 文本格式结束 `#!`（CK3/Vic3）
+    FormatEnd(&'a str),
+    /// 文本图标，如 `@gold_icon!`（CK3/Vic3）
+    TextIcon(&'a str),
+}
+
+impl<'a> MarkupToken<'a> {
+    /// 片段在原文中的文本
+    pub fn as_str(&self) -> &'a str {
+        match self {
+            MarkupToken::Text(s)
+            | MarkupToken::Escape(s)
+            | MarkupToken::ColorStart(s)
+            | MarkupToken::ColorEnd(s)
+            | MarkupToken::Variable(s)
+            | MarkupToken::Icon(s)
+            | MarkupToken::Command(s)
+            | MarkupToken::UnclosedCommand(s)
+            | MarkupToken::FormatStart(s)
+            | MarkupToken::FormatEnd(s)
+            | MarkupToken::TextIcon(s) => s,
+            MarkupToken::Concept { raw, .. } => raw,
+        }
+    }
+}
+
+/// 文本中的标记结构问题
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MarkupIssue {
+    /// 颜色没有以 `§!` 结束
+    UnclosedColor(String),
+    /// 多余的 `§!`
+    UnmatchedColorEnd,
+    /// 命令没有以 `]` 结束
+    UnclosedCommand,
+    /// 文本格式没有以 `#!` 结束
+    UnclosedFormat(String),
+    /// 多余的 `#!`
+    UnmatchedFormatEnd,
+}
+
+impl Display for MarkupIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkupIssue::UnclosedColor(code) => {
+                write!(f, "colour '{}' is not closed by '§!'", code)
+            }
+            MarkupIssue::UnmatchedColorEnd => write!(f, "'§!' has no matching colour code"),
+            MarkupIssue::UnclosedCommand => write!(f, "'[' is not closed by ']'"),
+            MarkupIssue::UnclosedFormat(format) => {
+                write!(f, "format '{}' is not closed by '#!'", format)
+            }
+            MarkupIssue::UnmatchedFormatEnd => write!(f, "'#!' has no matching format"),
+        }
+    }
+}
+
+/// 标记的类别，验证时同类标记之间相互比较
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MarkupKind {
+    Color,
+    Variable,
+    Icon,
+    Command,
+    Concept,
+    Format,
+    TextIcon,
+}
+
+/// 游戏支持的标记语法，所有游戏都支持颜色、参数、图标与命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkupSyntax {
+    /// 概念命令 `['concept' text]`（Stellaris）
+    pub concept_commands: bool,
+    /// 文本格式 `#format … #!`（CK3/Vic3）
+    pub format_blocks: bool,
+    /// 文本图标 `@icon!`（CK3/Vic3）
+    pub text_icons: bool,
+}
+
+impl Default for MarkupSyntax {
+    /// 默认为 Stellaris 的标记语法
+    fn default() -> Self {
+        Self::STELLARIS
+    }
+}
+
+/// 参数名中允许的字符
+fn is_variable_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '@' | ':')
+}
+
+/// 文本格式名中允许的字符，如 `#high;tooltippable`、`#tooltip:faith,key`
+fn is_format_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | ';' | ':' | ',' | '.')
+}
+
+impl MarkupSyntax {
+    /// Stellaris：支持概念命令
+    pub const STELLARIS: Self = Self {
+        concept_commands: true,
+        format_blocks: false,
+        text_icons: false,
+    };
+
+    /// CK3/Vic3：支持文本格式与文本图标
+    pub const JOMINI: Self = Self {
+        concept_commands: false,
+        format_blocks: true,
+        text_icons: true,
+    };
+
+    /// HOI4/EU4：只有基础标记
+    pub const CLAUSEWITZ: Self = Self {
+        concept_commands: false,
+        format_blocks: false,
+        text_icons: false,
+    };
+
+    /// 将文本切分为片段
+    pub fn tokenize<'a>(&self, text: &'a str) -> Vec<MarkupToken<'a>> {
+        let mut tokens = Vec::new();
+        let mut text_start = 0;
+        let mut pos = 0;
+        while let Some(c) = text[pos..].chars().next() {
+            let rest = &text[pos..];
+            let markup_len = match c {
+                '\\' => rest[1..].chars().next().map(|next| 1 + next.len_utf8()),
+                '§' => rest[c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .map(|next| c.len_utf8() + next.len_utf8()),
+                '$' => scan_variable(rest),
+                '£' => scan_icon(rest),
+                '[' => Some(scan_command(rest).unwrap_or(1)),
+                '#' if self.format_blocks => scan_format(rest),
+                '@' if self.text_icons => scan_text_icon(rest),
+                _ => None,
+            };
+            let Some(len) = markup_len else {
+                pos += c.len_utf8();
+                continue;
+            };
+            if text_start < pos {
+                tokens.push(MarkupToken::Text(&text[text_start..pos]));
+            }
+            let raw = &rest[..len];
+            let token = match c {
+                '\\' => MarkupToken::Escape(raw),
+                '§' if raw.ends_with('!') => MarkupToken::ColorEnd(raw),
+                '§' => MarkupToken::ColorStart(raw),
+                '$' => MarkupToken::Variable(raw),
+                '£' => MarkupToken::Icon(raw),
+                '#' if raw == "#!" => MarkupToken::FormatEnd(raw),
+                '#' => MarkupToken::FormatStart(raw),
+                '@' => MarkupToken::TextIcon(raw),
+                _ if raw == "[" => MarkupToken::UnclosedCommand(raw),
+                _ => self.command_token(raw),
+            };
+            tokens.push(token);
+            pos += len;
+            text_start = pos;
+        }
+        if text_start < text.len() {
+            tokens.push(MarkupToken::Text(&text[text_start..]));
+        }
+        tokens
+    }
+
+    /// 区分普通命令和概念命令 `['concept' text]`、`['concept', text]`
+    fn command_token<'a>(&self, raw: &'a str) -> MarkupToken<'a> {
+        let inner = &raw[1..raw.len() - 1];
+        if self.concept_commands
+            && let Some(quoted) = inner.strip_prefix('\'')
+            && let Some(end) = quoted.find('\'')
+        {
+            let key = &quoted[..end];
+            let text = quoted[end + 1..]
+                .trim_start_matches(',')
+                .trim_start_matches(' ');
+            return MarkupToken::Concept { raw, key, text };
+        }
+        MarkupToken::Command(raw)
+    }
+
+    /// 文本中的所有标记（包括概念命令说明文本中的标记），按类别与出现顺序排列
+    ///
+    /// 概念命令只比较概念的 key，说明文本是需要翻译的内容
+    pub fn items(&self, text: &str) -> Vec<(MarkupKind, String)> {
+        let mut items = Vec::new();
+        for token in self.tokenize(text) {
+            let item = match token {
+                MarkupToken::ColorStart(s) => (MarkupKind::Color, s.to_string()),
+                MarkupToken::Variable(s) => (MarkupKind::Variable, s.to_string()),
+                MarkupToken::Icon(s) => (MarkupKind::Icon, s.to_string()),
+                MarkupToken::Command(s) => (MarkupKind::Command, s.to_string()),
+                MarkupToken::FormatStart(s) => (MarkupKind::Format, s.to_string()),
+                MarkupToken::TextIcon(s) => (MarkupKind::TextIcon, s.to_string()),
+                MarkupToken::Concept { key, text, .. } => {
+                    items.extend(self.items(text));
+                    (MarkupKind::Concept, format!("['{}' …]", key))
+                }
+                _ => continue,
+            };
+            items.push(item);
+        }
+        items
+    }
+
+    /// 去除标记后的纯文本，标记与转义序列替换为空格；概念命令保留其说明文本
+    pub fn plain_text(&self, text: &str) -> String {
+        let mut plain = String::with_capacity(text.len());
+        for token in self.tokenize(text) {
+            match token {
+                MarkupToken::Text(s) => plain.push_str(s),
+                MarkupToken::Concept { text, .. } => plain.push_str(&self.plain_text(text)),
+                _ => plain.push(' '),
+            }
+        }
+        plain
+    }
+
+    /// 检查文本中颜色、文本格式与命令的配对情况
+    pub fn issues(&self, text: &str) -> Vec<MarkupIssue> {
+        let mut issues = Vec::new();
+        let mut colors: Vec<&str> = Vec::new();
+        let mut formats: Vec<&str> = Vec::new();
+        self.collect_issues(&self.tokenize(text), &mut colors, &mut formats, &mut issues);
+        issues.extend(
+            colors
+                .into_iter()
+                .map(|code| MarkupIssue::UnclosedColor(code.to_string())),
+        );
+        issues.extend(
+            formats
+                .into_iter()
+                .map(|format| MarkupIssue::UnclosedFormat(format.to_string())),
+        );
+        issues
+    }
+
+    fn collect_issues<'a>(
+        &self,
+        tokens: &[MarkupToken<'a>],
+        colors: &mut Vec<&'a str>,
+        formats: &mut Vec<&'a str>,
+        issues: &mut Vec<MarkupIssue>,
+    ) {
+        for token in tokens {
+            match token {
+                MarkupToken::ColorStart(code) => colors.push(code),
+                MarkupToken::ColorEnd(_) => {
+                    let closed = colors.pop();
+                    if closed.is_none() {
+                        issues.push(MarkupIssue::UnmatchedColorEnd);
+                    }
+                }
+                MarkupToken::FormatStart(format) => formats.push(format),
+                MarkupToken::FormatEnd(_) => {
+                    let closed = formats.pop();
+                    if closed.is_none() {
+                        issues.push(MarkupIssue::UnmatchedFormatEnd);
+                    }
+                }
+                MarkupToken::Concept { text, .. } => {
+                    self.collect_issues(&self.tokenize(text), colors, formats, issues)
+                }
+                MarkupToken::UnclosedCommand(_) => issues.push(MarkupIssue::UnclosedCommand),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// `$name$` 或 `$name|arg$`，名称不能为空且不含空白；不是参数时（如 "cost $5"）返回 None
+fn scan_variable(text: &str) -> Option<usize> {
+    let rest = &text[1..];
+    let name_len = rest
+        .char_indices()
+        .find(|(_, c)| !is_variable_char(*c))
+        .map(|(i, _)| i)
+        .unwrap_or(rest.len());
+    if name_len == 0 {
+        return None;
+    }
+    let after_name = &rest[name_len..];
+    if after_name.starts_with('$') {
+        return Some(1 + name_len + 1);
+    }
+    let arg = after_name.strip_prefix('|')?;
+    let end = arg.find(|c: char| c == '$' || c.is_whitespace())?;
+    arg[end..]
+        .starts_with('$')
+        .then_some(1 + name_len + 1 + end + 1)
+}
+
+/// `£icon£` 或 `£icon|frame£`，图标名不含空白
+fn scan_icon(text: &str) -> Option<usize> {
+    let rest = &text['£'.len_utf8()..];
+    let end = rest.find(|c: char| c == '£' || c.is_whitespace())?;
+    (end > 0 && rest[end..].starts_with('£')).then_some('£'.len_utf8() * 2 + end)
+}
+
+/// 匹配嵌套的方括号，返回包含最外层 `]` 的长度；没有闭合时返回 None
+fn scan_command(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `#!` 或以字母开头的 `#format`；其他情况（如 "#1"）返回 None
+fn scan_format(text: &str) -> Option<usize> {
+    let rest = &text[1..];
+    if rest.starts_with('!') {
+        return Some(2);
+    }
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let len = rest
+        .find(|c: char| !is_format_char(c))
+        .unwrap_or(rest.len());
+    Some(1 + len)
+}
+
+/// `@icon!`，图标名只含字母、数字和下划线
+fn scan_text_icon(text: &str) -> Option<usize> {
+    let rest = &text[1..];
+    let len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    (len > 0 && rest[len..].starts_with('!')).then_some(1 + len + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STELLARIS_TEXT: &str = r#"§Y$target|Y$§! costs $5 £energy£ [Root.GetName] ['pop_growth', §G+10%§! [This.GetName]]\n"#;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            MarkupSyntax::STELLARIS.tokenize(STELLARIS_TEXT),
+            vec![
+                MarkupToken::ColorStart("§Y"),
+                MarkupToken::Variable("$target|Y$"),
+                MarkupToken::ColorEnd("§!"),
+                MarkupToken::Text(" costs $5 "),
+                MarkupToken::Icon("£energy£"),
+                MarkupToken::Text(" "),
+                MarkupToken::Command("[Root.GetName]"),
+                MarkupToken::Text(" "),
+                MarkupToken::Concept {
+                    raw: "['pop_growth', §G+10%§! [This.GetName]]",
+                    key: "pop_growth",
+                    text: "§G+10%§! [This.GetName]",
+                },
+                MarkupToken::Escape(r"\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markup_items() {
+        assert_eq!(
+            MarkupSyntax::STELLARIS
+                .items(STELLARIS_TEXT)
+                .into_iter()
+                .map(|(_, s)| s)
+                .collect::<Vec<_>>(),
+            [
+                "§Y",
+                "$target|Y$",
+                "£energy£",
+                "[Root.GetName]",
+                "§G",
+                "[This.GetName]",
+                "['pop_growth' …]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markup_issues() {
+        let syntax = MarkupSyntax::STELLARIS;
+        assert!(syntax.issues(STELLARIS_TEXT).is_empty());
+        assert_eq!(
+            syntax.issues("§H text §! §! [Root.Get"),
+            [MarkupIssue::UnmatchedColorEnd, MarkupIssue::UnclosedCommand]
+        );
+        assert_eq!(
+            syntax.issues("§H text"),
+            [MarkupIssue::UnclosedColor("§H".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_jomini() {
+        let syntax = MarkupSyntax::JOMINI;
+        let text = "#bold $VALUE|=+0$#! @gold_icon! #1 ['x'] email@host";
+        assert_eq!(
+            syntax.tokenize(text),
+            vec![
+                MarkupToken::FormatStart("#bold"),
+                MarkupToken::Text(" "),
+                MarkupToken::Variable("$VALUE|=+0$"),
+                MarkupToken::FormatEnd("#!"),
+                MarkupToken::Text(" "),
+                MarkupToken::TextIcon("@gold_icon!"),
+                MarkupToken::Text(" #1 "),
+                MarkupToken::Command("['x']"),
+                MarkupToken::Text(" email@host"),
+            ]
+        );
+        assert_eq!(
+            syntax.issues("#high text #! #!"),
+            [MarkupIssue::UnmatchedFormatEnd]
+        );
+    }
+
+    #[test]
+    fn test_stellaris_ignores_jomini_markup() {
+        assert_eq!(
+            MarkupSyntax::STELLARIS.tokenize("#bold @icon!"),
+            [MarkupToken::Text("#bold @icon!")]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! 标记占位符模块
+//!
+//! 发送给大模型前可以将条目值中的颜色、参数、图标、命令等标记替换为 `⟪N⟫` 占位符，
+//! 收到译文后检查每个占位符是否恰好出现一次，再还原为原来的标记。
+//! 大模型只能调整占位符的位置，无法改写标记本身。
+//! 概念命令 `['concept' text]` 只替换说明文本之外的部分，说明文本仍需翻译。
+
+use crate::preprocess::value_range;
+use crate::translate::markup::{MarkupSyntax, MarkupToken};
+use regex::Regex;
+
+/// 标记替换为占位符后的文本
+#[derive(Debug, Clone, Default)]
+pub struct MaskedMarkup {
+    /// 替换后的文本
+    pub text: String,
+    /// 按占位符编号（从 1 开始）排列的标记
+    pub originals: Vec<String>,
+}
+
+impl MaskedMarkup {
+    /// 将本地化内容中条目值里的标记替换为占位符，键名和注释保持不变
+    pub fn mask(markup: MarkupSyntax, content: &str) -> Self {
+        let mut masked = Self::default();
+        for line in content.split_inclusive('\n') {
+            let Some(value_range) = value_range(line) else {
+                masked.text.push_str(line);
+                continue;
+            };
+            masked.text.push_str(&line[..value_range.start]);
+            masked.mask_value(markup, &line[value_range.clone()]);
+            masked.text.push_str(&line[value_range.end..]);
+        }
+        masked
+    }
+
+    fn mask_value(&mut self, markup: MarkupSyntax, value: &str) {
+        for token in markup.tokenize(value) {
+            match token {
+                MarkupToken::Text(s) | MarkupToken::Escape(s) | MarkupToken::UnclosedCommand(s) => {
+                    self.text.push_str(s)
+                }
+                MarkupToken::Concept { raw, text, .. } => {
+                    // 说明文本位于 `]` 之前
+                    let text_start = raw.len() - 1 - text.len();
+                    self.push_placeholder(&raw[..text_start]);
+                    self.mask_value(markup, text);
+                    self.push_placeholder(&raw[raw.len() - 1..]);
+                }
+                token => self.push_placeholder(token.as_str()),
+            }
+        }
+    }
+
+    fn push_placeholder(&mut self, original: &str) {
+        self.originals.push(original.to_string());
+        self.text.push_str(&format!("⟪{}⟫", self.originals.len()));
+    }
+
+    /// 检查译文中每个占位符是否恰好出现一次，并还原为原来的标记
+    ///
+    /// 存在缺失、重复或未知的占位符时返回这些占位符及其出现次数
+    pub fn unmask(&self, text: &str) -> Result<String, Vec<(String, usize)>> {
+        let placeholder = Regex::new(r"⟪(\d+)⟫").unwrap();
+        let mut counts = vec![0usize; self.originals.len()];
+        let mut unknown: Vec<(String, usize)> = Vec::new();
+        for caps in placeholder.captures_iter(text) {
+            let index = caps[1].parse::<usize>().ok().and_then(|n| n.checked_sub(1));
+            match index.and_then(|i| counts.get_mut(i)) {
+                Some(count) => *count += 1,
+                None => match unknown.iter_mut().find(|(s, _)| s == &caps[0]) {
+                    Some((_, count)) => *count += 1,
+                    None => unknown.push((caps[0].to_string(), 1)),
+                },
+            }
+        }
+        let mut errors: Vec<(String, usize)> = counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count != 1)
+            .map(|(i, count)| (format!("⟪{}⟫", i + 1), *count))
+            .collect();
+        errors.extend(unknown);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(placeholder
+            .replace_all(text, |caps: &regex::Captures| {
+                let i: usize = caps[1].parse().unwrap();
+                self.originals[i - 1].clone()
+            })
+            .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = concat!(
+        "# §Y注释§!\n",
+        " a:0 \"§Y$NAME$§! produces £unity£\\n[Root.GetName]\"\n",
+        " b:0 \"['pop_growth', §GGrowth§!] costs 5$\"\n",
+    );
+
+    fn masked() -> MaskedMarkup {
+        MaskedMarkup::mask(MarkupSyntax::STELLARIS, CONTENT)
+    }
+
+    #[test]
+    fn test_mask_markup_in_values() {
+        let masked = masked();
+        assert_eq!(
+            masked.text,
+            concat!(
+                "# §Y注释§!\n",
+                " a:0 \"⟪1⟫⟪2⟫⟪3⟫ produces ⟪4⟫\\n⟪5⟫\"\n",
+                " b:0 \"⟪6⟫⟪7⟫Growth⟪8⟫⟪9⟫ costs 5$\"\n",
+            )
+        );
+        assert_eq!(masked.originals[5], "['pop_growth', ");
+    }
+
+    #[test]
+    fn test_unmask_reordered_placeholders() {
+        let translated = " a:0 \"⟪4⟫由⟪1⟫⟪2⟫⟪3⟫产出\\n⟪5⟫\"\n b:0 \"⟪6⟫⟪7⟫增长⟪8⟫⟪9⟫花费 5$\"\n";
+        assert_eq!(
+            masked().unmask(translated).unwrap(),
+            " a:0 \"£unity£由§Y$NAME$§!产出\\n[Root.GetName]\"\n b:0 \"['pop_growth', §G增长§!]花费 5$\"\n"
+        );
+    }
+
+    #[test]
+    fn test_unmask_reports_broken_placeholders() {
+        let broken = " a:0 \"⟪4⟫⟪4⟫由⟪1⟫⟪2⟫⟪3⟫产出\\n⟪12⟫\"\n b:0 \"⟪6⟫⟪7⟫增长⟪8⟫⟪9⟫\"\n";
+        assert_eq!(
+            masked().unmask(broken).unwrap_err(),
+            [
+                ("⟪4⟫".to_string(), 2),
+                ("⟪5⟫".to_string(), 0),
+                ("⟪12⟫".to_string(), 1)
+            ]
+        );
+    }
+}
@@ -4,14 +4,30 @@
 
 mod api;
 mod batcher;
+mod do_not_translate;
 mod glossary;
+mod glossary_harvest;
+mod glossary_io;
+mod glossary_lint;
+mod key_index;
+mod markup;
+mod markup_mask;
 mod splitter;
+mod term_extractor;
 mod translator;
 mod validator;
 
 pub use api::*;
 pub use batcher::*;
+pub use do_not_translate::*;
 pub use glossary::*;
+pub use glossary_harvest::*;
+pub use glossary_io::*;
+pub use glossary_lint::*;
+pub use key_index::*;
+pub use markup::*;
+pub use markup_mask::*;
 pub use splitter::*;
+pub use term_extractor::*;
 pub use translator::*;
 pub use validator::*;
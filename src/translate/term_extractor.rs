@@ -0,0 +1,224 @@
+//! 术语提取模块
+//!
+//! 在正式翻译前，从 MOD 的源文本中挖掘 MOD 自创的名词（如 "Fungal Sanctuary"），
+//! 以便先单独翻译这些术语，再将其作为术语表用于正式翻译，保证前后译法一致。
+
+use crate::preprocess::LocalisationEntry;
+use crate::translate::glossary::Glossary;
+use crate::translate::glossary_harvest::{is_name_key, is_name_value, split_words};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+/// 首字母大写词组中不计入的虚词
+const STOPWORDS: [&str; 12] = [
+    "a", "an", "the", "of", "and", "or", "to", "in", "on", "for", "with", "by",
+];
+
+/// 候选术语的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermSource {
+    /// 名称类条目的值
+    NameEntry(String),
+    /// 文本中反复出现的首字母大写词组
+    CapitalisedPhrase,
+    /// 文本中以 `$key$` 引用的名称类条目
+    KeyReference(String),
+}
+
+/// 候选术语
+#[derive(Debug, Clone)]
+pub struct TermCandidate {
+    /// 术语原文（保留大小写）
+    pub term: String,
+    /// 在源文本中出现的次数
+    pub occurrences: usize,
+    pub source: TermSource,
+}
+
+/// 术语提取器
+pub struct TermExtractor {
+    /// 候选术语的最大单词数
+    max_words: usize,
+    markup_pattern: Regex,
+    reference_pattern: Regex,
+}
+
+impl Default for TermExtractor {
+    fn default() -> Self {
+        Self {
+            max_words: 4,
+            markup_pattern: Regex::new(r"§.|£[^£]*£|\$[^$]*\$|\[[^\]]*\]|\\n").unwrap(),
+            reference_pattern: Regex::new(r"\$([A-Za-z0-9_.]+)(?:\|[^$]*)?\$").unwrap(),
+        }
+    }
+}
+
+impl TermExtractor {
+    /// 创建新的提取器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从源条目中提取候选术语，跳过术语表中已有目标语言译法的术语，结果按术语排序
+    pub fn extract(
+        &self,
+        entries: &[LocalisationEntry],
+        glossary: &Glossary,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Vec<TermCandidate> {
+        let values: HashMap<&str, &str> = entries
+            .iter()
+            .map(|e| (e.key.as_str(), e.value.as_str()))
+            .collect();
+        let plain: Vec<String> = entries
+            .iter()
+            .map(|e| self.markup_pattern.replace_all(&e.value, " ").to_string())
+            .collect();
+        let known: HashSet<String> = glossary
+            .get_translation_map(source_lang, target_lang)
+            .into_keys()
+            .map(|term| term.to_lowercase())
+            .collect();
+
+        let mut candidates: HashMap<String, TermCandidate> = HashMap::new();
+        let mut add = |term: &str, source: TermSource| {
+            let term = term.trim();
+            let lower = term.to_lowercase();
+            if term.is_empty() || known.contains(&lower) {
+                return;
+            }
+            candidates.entry(lower).or_insert_with(|| TermCandidate {
+                term: term.to_string(),
+                occurrences: 0,
+                source,
+            });
+        };
+
+        // 1. 名称类条目
+        for entry in entries {
+            if is_name_key(&entry.key) && is_name_value(&entry.value, self.max_words) {
+                add(&entry.value, TermSource::NameEntry(entry.key.clone()));
+            }
+        }
+
+        // 2. 引用了名称类条目的 $key$
+        for entry in entries {
+            for caps in self.reference_pattern.captures_iter(&entry.value) {
+                let key = &caps[1];
+                if let Some(value) = values.get(key)
+                    && is_name_value(value, self.max_words)
+                {
+                    add(value, TermSource::KeyReference(key.to_string()));
+                }
+            }
+        }
+
+        // 3. 至少出现两次的首字母大写词组
+        let mut phrases: HashMap<String, (String, usize)> = HashMap::new();
+        for text in &plain {
+            for phrase in self.capitalised_phrases(text) {
+                let counter = phrases
+                    .entry(phrase.to_lowercase())
+                    .or_insert_with(|| (phrase.clone(), 0));
+                counter.1 += 1;
+            }
+        }
+        for (phrase, count) in phrases.into_values() {
+            if count >= 2 {
+                add(&phrase, TermSource::CapitalisedPhrase);
+            }
+        }
+
+        // 统计出现次数
+        let lowered: Vec<String> = plain
+            .iter()
+            .map(|text| split_words(text).join(" "))
+            .collect();
+        let mut result: Vec<TermCandidate> = candidates
+            .into_values()
+            .map(|mut candidate| {
+                let needle = split_words(&candidate.term).join(" ");
+                candidate.occurrences = lowered
+                    .iter()
+                    .filter(|text| contains_phrase(text, &needle))
+                    .count();
+                candidate
+            })
+            .collect();
+        result.sort_by_key(|candidate| candidate.term.to_lowercase());
+        result
+    }
+
+    /// 提取文本中连续的首字母大写单词组成的词组（2 至 max_words 个单词）
+    fn capitalised_phrases(&self, text: &str) -> Vec<String> {
+        let mut phrases = Vec::new();
+        let mut run: Vec<&str> = Vec::new();
+        let words = text.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '\''));
+        for word in words.chain(std::iter::once("")) {
+            let capitalised = word.chars().next().is_some_and(|c| c.is_uppercase());
+            if capitalised {
+                run.push(word);
+                continue;
+            }
+            // 去掉首尾的虚词
+            while run
+                .first()
+                .is_some_and(|w| STOPWORDS.contains(&w.to_lowercase().as_str()))
+            {
+                run.remove(0);
+            }
+            if run.len() >= 2 && run.len() <= self.max_words {
+                phrases.push(run.join(" "));
+            }
+            run.clear();
+        }
+        phrases
+    }
+}
+
+/// 按单词边界判断 text 是否包含 phrase（二者均为空格分隔的小写单词）
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    if phrase.is_empty() {
+        return false;
+    }
+    format!(" {} ", text).contains(&format!(" {} ", phrase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocess::parse_entries;
+    use crate::translate::glossary::GlossaryItem;
+
+    #[test]
+    fn test_extract_terms() {
+        let entries = parse_entries(
+            r#"l_english:
+ sr_zro:0 "Zro"
+ zone_fungi:0 "Fungal Sanctuary"
+ zone_fungi_desc:0 "The Fungal Sanctuary calms the Psionic Choir. It needs $sr_zro$."
+ tip:0 "Visit the Psionic Choir at dawn."
+ energy:0 "Energy"
+"#,
+        );
+        let mut glossary = Glossary::default();
+        let mut item = GlossaryItem::default();
+        item.set("english", Some("energy".to_string())).unwrap();
+        item.set("simp_chinese", Some("能量".to_string())).unwrap();
+        glossary.insert("energy".to_string(), item).unwrap();
+
+        let candidates =
+            TermExtractor::new().extract(&entries, &glossary, "english", "simp_chinese");
+        let terms: Vec<&str> = candidates.iter().map(|c| c.term.as_str()).collect();
+        assert_eq!(terms, vec!["Fungal Sanctuary", "Psionic Choir", "Zro"]);
+
+        let sanctuary = &candidates[0];
+        assert_eq!(sanctuary.occurrences, 2);
+        assert_eq!(
+            sanctuary.source,
+            TermSource::NameEntry("zone_fungi".to_string())
+        );
+        assert_eq!(candidates[1].source, TermSource::CapitalisedPhrase);
+    }
+}
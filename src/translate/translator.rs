@@ -2,21 +2,50 @@
 //!
 //! 集成API客户端、术语表和提示词模板，执行翻译任务。
 
-use crate::config::ClientSettings;
+use crate::config::{
+    ClientSettings, Game, GameProfile, GlossaryConflictPolicy, ValidationSettings,
+};
 use crate::error::{Result, TranslationError};
-use crate::postprocess::TranslationSlice;
+use crate::postprocess::{TranslationSlice, fix_markup};
+use crate::preprocess::parse_entries;
 use crate::translate::FileChunk;
 use crate::translate::api::{ApiClient, system_message, user_message};
+use crate::translate::do_not_translate::DoNotTranslate;
 use crate::translate::glossary::Glossary;
-use crate::translate::validator::FormatValidator;
+use crate::translate::glossary_lint::merge_glossaries_with_policy;
+use crate::translate::key_index::KeyIndex;
+use crate::translate::markup_mask::MaskedMarkup;
+use crate::translate::validator::Validators;
 use crate::utils::{estimate_mixed_tokens, find_data_file_or_error};
+use std::collections::HashMap;
 use std::fs;
+use std::sync::{Arc, RwLock};
+
+/// 正文翻译的系统提示词模板
+const TRANSLATE_PROMPT: &str = "translate_system.txt";
+/// 术语预翻译的系统提示词模板
+const TRANSLATE_TERMS_PROMPT: &str = "translate_terms_system.txt";
+/// 术语预翻译时每次请求包含的术语数
+const TERMS_PER_REQUEST: usize = 100;
 
 /// 翻译器
 pub struct Translator {
     api_client: ApiClient,
     glossary: Glossary,
-    validator: FormatValidator,
+    /// 源文件键索引，翻译过程中会持续记录新产生的译文
+    key_index: RwLock<KeyIndex>,
+    /// 禁止翻译列表，发送前替换为占位符
+    do_not_translate: DoNotTranslate,
+    /// 游戏配置，决定提示词中的标记说明与格式验证规则
+    profile: GameProfile,
+    /// 译文检查的设置
+    validation: ValidationSettings,
+    /// 是否将标记替换为占位符后再发送给大模型
+    mask_markup: bool,
+    /// 是否在翻译后按原文修复被破坏的标记
+    fix_markup: bool,
+    /// 按（源语言，目标语言）缓存的验证器，术语表等设置改变时清空
+    validators: RwLock<HashMap<(String, String), Arc<Validators>>>,
 }
 
 impl Translator {
@@ -25,7 +54,13 @@ impl Translator {
         Self {
             api_client,
             glossary: glossaries,
-            validator: FormatValidator::new(),
+            key_index: RwLock::new(KeyIndex::default()),
+            do_not_translate: DoNotTranslate::default(),
+            profile: Game::default().profile(),
+            validation: ValidationSettings::default(),
+            mask_markup: false,
+            fix_markup: false,
+            validators: RwLock::new(HashMap::new()),
         }
     }
 
@@ -36,9 +71,110 @@ impl Translator {
         Ok(Self::new(api_client, glossary))
     }
 
+    /// 获取翻译器使用的术语表
+    pub fn glossary(&self) -> &Glossary {
+        &self.glossary
+    }
+
+    /// 设置用于展开 `$key$` 引用的键索引
+    pub fn set_key_index(&mut self, key_index: KeyIndex) {
+        self.key_index = RwLock::new(key_index);
+    }
+
+    /// 设置要翻译的游戏
+    pub fn set_game(&mut self, game: Game) {
+        self.profile = game.profile();
+        self.clear_validators();
+    }
+
+    /// 设置译文检查的设置
+    pub fn set_validation(&mut self, validation: ValidationSettings) {
+        self.validation = validation;
+        self.clear_validators();
+    }
+
+    /// 设置是否将标记替换为占位符后再发送给大模型
+    pub fn set_mask_markup(&mut self, mask_markup: bool) {
+        self.mask_markup = mask_markup;
+    }
+
+    /// 设置是否在翻译后按原文修复被破坏的标记
+    pub fn set_fix_markup(&mut self, fix_markup: bool) {
+        self.fix_markup = fix_markup;
+    }
+
+    /// 设置禁止翻译列表
+    pub fn set_do_not_translate(&mut self, do_not_translate: DoNotTranslate) {
+        self.do_not_translate = do_not_translate;
+        self.clear_validators();
+    }
+
+    /// 记录已翻译完成的文件内容，使后续引用这些键的文本能看到其译文
+    pub fn record_translations(&self, target_lang: &str, content: &str) {
+        self.key_index
+            .write()
+            .expect("key index lock poisoned")
+            .record_translations(target_lang, parse_entries(content));
+    }
+
+    /// 将新的术语并入翻译器的术语表，按任务的冲突处理策略合并
+    pub fn extend_glossary(
+        &mut self,
+        glossary: &Glossary,
+        source_lang: &str,
+        policy: GlossaryConflictPolicy,
+    ) -> Result<()> {
+        let (merged, conflicts) = merge_glossaries_with_policy(
+            &[self.glossary.clone(), glossary.clone()],
+            source_lang,
+            policy,
+        )?;
+        for conflict in &conflicts {
+            log::warn!("Glossary conflict: {}", conflict);
+        }
+        self.glossary = merged;
+        self.clear_validators();
+        Ok(())
+    }
+
+    /// 某一目标语言的验证器，第一次使用时创建
+    fn validators(&self, source_lang: &str, target_lang: &str) -> Arc<Validators> {
+        let key = (source_lang.to_string(), target_lang.to_string());
+        if let Some(validators) = self
+            .validators
+            .read()
+            .expect("validators lock poisoned")
+            .get(&key)
+        {
+            return validators.clone();
+        }
+        let validators = Arc::new(Validators::new(
+            &self.glossary,
+            &self.do_not_translate,
+            self.profile.markup,
+            &self.validation,
+            source_lang,
+            target_lang,
+        ));
+        self.validators
+            .write()
+            .expect("validators lock poisoned")
+            .entry(key)
+            .or_insert(validators)
+            .clone()
+    }
+
+    fn clear_validators(&mut self) {
+        self.validators
+            .get_mut()
+            .expect("validators lock poisoned")
+            .clear();
+    }
+
     /// 加载系统提示词模板
     fn load_system_prompt(
         &self,
+        template: &str,
         source_lang: &str,
         target_lang: &str,
         source_text: &str,
@@ -46,7 +182,7 @@ impl Translator {
         // 数据目录应按照以下顺序寻找，若不存在再寻找下一个：
         // 1. 当前目录下的提示词： ./data/
         // 2. 用户级数据目录下的提示词： ~/.local/share/pmt/data/
-        let prompt_path = find_data_file_or_error("prompts/translate_system.txt")?;
+        let prompt_path = find_data_file_or_error(&format!("prompts/{}", template))?;
         let mut prompt = fs::read_to_string(&prompt_path).map_err(|e| {
             TranslationError::Translate(crate::error::TranslateError::ValidationFailed(format!(
                 "Failed to load prompt template from {}: {}",
@@ -55,6 +191,12 @@ impl Translator {
             )))
         })?;
 
+        // 填入游戏配置
+        prompt = prompt
+            .replace("{{game_name}}", self.profile.name)
+            .replace("{{style}}", self.profile.style)
+            .replace("{{markup_rules}}", &self.profile.markup_rules());
+
         // 提取源文本中的术语
         let mut all_found_terms = Vec::new();
         let found_terms = self.glossary.find_terms_in_text(source_text, source_lang);
@@ -71,16 +213,14 @@ impl Translator {
             // 合并所有术语表的术语
             let mut terms_count = 0;
             let mut csv_data = String::new();
-            csv_data.push_str(&format!("{},{}", source_lang, target_lang));
 
-            let source_terms: Vec<&str> = all_found_terms.iter().map(|s| s.as_str()).collect();
+            let keys: Vec<&str> = all_found_terms.iter().map(|s| s.as_str()).collect();
 
-            let csv = self
-                .glossary
-                .to_csv(source_lang, target_lang, &source_terms);
+            let csv = self.glossary.to_csv(source_lang, target_lang, &keys);
             if !csv.is_empty() && csv.contains('\n') {
-                // 跳过表头行（第一行）
+                // 表头行（第一行）之后为术语
                 let lines: Vec<&str> = csv.lines().collect();
+                csv_data.push_str(lines[0]);
                 if lines.len() > 1 {
                     for line in &lines[1..] {
                         if !line.trim().is_empty() {
@@ -107,6 +247,22 @@ impl Translator {
             prompt = prompt.replace("{{glossary_csv}}", "（无相关术语）");
         }
 
+        // 展开源文本中引用的其他条目
+        let referenced = self
+            .key_index
+            .read()
+            .expect("key index lock poisoned")
+            .format_references(source_text, target_lang);
+        if !referenced.is_empty() {
+            log::debug!(
+                "\n======DEBUG Using referenced keys======\n{}\n======DEBUG END======\n",
+                &referenced
+            );
+            prompt = prompt.replace("{{referenced_keys}}", &referenced);
+        } else {
+            prompt = prompt.replace("{{referenced_keys}}", "（无引用的条目）");
+        }
+
         Ok(prompt)
     }
 
@@ -117,14 +273,86 @@ impl Translator {
         source_lang: &str,
         target_lang: &str,
     ) -> Result<TranslationSlice> {
-        // 加载系统提示词
         let source_text = &chunk.content;
-        let system_prompt = self.load_system_prompt(source_lang, target_lang, source_text)?;
+        let mut translated_text = if self.mask_markup {
+            // 将标记替换为占位符，译文中的占位符没有恰好出现一次时，不替换标记重新翻译
+            let masked = MaskedMarkup::mask(self.profile.markup, source_text);
+            let translated = self
+                .request_translation(chunk, source_lang, target_lang, &masked.text)
+                .await?;
+            match masked.unmask(&translated) {
+                Ok(text) => text,
+                Err(errors) => {
+                    let errors: Vec<String> = errors
+                        .iter()
+                        .map(|(placeholder, count)| format!("{} x{}", placeholder, count))
+                        .collect();
+                    log::warn!(
+                        "Markup placeholders in {} were not returned exactly once ({}), retrying without masking markup",
+                        chunk.target_filename,
+                        errors.join(", ")
+                    );
+                    self.request_translation(chunk, source_lang, target_lang, source_text)
+                        .await?
+                }
+            }
+        } else {
+            self.request_translation(chunk, source_lang, target_lang, source_text)
+                .await?
+        };
+
+        if self.fix_markup {
+            let (fixed, fixes) = fix_markup(self.profile.markup, source_text, &translated_text);
+            for fix in &fixes {
+                log::info!("Fixed markup in {}: {}", chunk.target_filename, fix);
+            }
+            translated_text = fixed;
+        }
+
+        // 验证格式、术语与禁止翻译的文本
+        let checked = self
+            .validators(source_lang, target_lang)
+            .validate(source_text, &translated_text);
+
+        for problem in checked {
+            log::warn!("Found issue in {}: {}", &chunk.target_filename, problem);
+        }
+
+        let slice = TranslationSlice {
+            content: translated_text.to_owned(),
+            start_line: chunk.start_line,
+            end_line: chunk.end_line,
+        };
+        Ok(slice)
+    }
+
+    /// 将禁止翻译的文本替换为占位符后发送给大模型，返回还原占位符后的译文
+    async fn request_translation(
+        &self,
+        chunk: &FileChunk,
+        source_lang: &str,
+        target_lang: &str,
+        text: &str,
+    ) -> Result<String> {
+        // 将禁止翻译的文本替换为占位符
+        let masked = self.do_not_translate.mask(text);
+        if !masked.originals.is_empty() {
+            log::debug!(
+                "Masked {} protected texts in {}",
+                masked.originals.len(),
+                chunk.target_filename
+            );
+        }
+
+        // 加载系统提示词，其中的术语与引用的条目按未替换标记的原文查找
+        let prompt_text = self.do_not_translate.mask(&chunk.content).text;
+        let system_prompt =
+            self.load_system_prompt(TRANSLATE_PROMPT, source_lang, target_lang, &prompt_text)?;
 
         // 准备消息
         let messages = vec![
             system_message(system_prompt),
-            user_message(source_text.to_string()),
+            user_message(masked.text.clone()),
         ];
 
         let id = format!(
@@ -134,8 +362,8 @@ impl Translator {
         log::info!(
             "Sending translation request [{}] with {} characters, estimated {} tokens...",
             id,
-            source_text.chars().count(),
-            estimate_mixed_tokens(&source_text)
+            text.chars().count(),
+            estimate_mixed_tokens(text)
         );
         // 调用API
         let response = self.api_client.chat_completions(messages).await?;
@@ -159,20 +387,8 @@ impl Translator {
             .message
             .content
             .clone();
-
-        // 验证格式
-        let checked = self.validator.validate(&source_text, &translated_text);
-
-        for problem in checked {
-            log::warn!("Found issue in {}: {}", &chunk.target_filename, problem);
-        }
-
-        let slice = TranslationSlice {
-            content: translated_text.to_owned(),
-            start_line: chunk.start_line,
-            end_line: chunk.end_line,
-        };
-        Ok(slice)
+        // 还原占位符
+        Ok(masked.unmask(&translated_text))
     }
 
     /// 批量翻译文本片段
@@ -189,10 +405,8 @@ impl Translator {
         let mut handles = Vec::new();
         for chunk in chunks {
             let chunk = chunk.to_owned();
-            let handle = async move {
-                self.translate_chunk(&chunk, &source_lang, &target_lang)
-                    .await
-            };
+            let handle =
+                async move { self.translate_chunk(&chunk, source_lang, target_lang).await };
             handles.push(handle);
         }
         let translated = futures::future::join_all(handles).await;
@@ -216,4 +430,67 @@ impl Translator {
 
         Ok(results)
     }
+
+    /// 单独翻译一组术语，返回 (原文, 译文) 列表
+    ///
+    /// 模型未返回译文的术语会被跳过并记录警告
+    pub async fn translate_terms(
+        &self,
+        terms: &[String],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let mut results = Vec::new();
+        for (batch_index, batch) in terms.chunks(TERMS_PER_REQUEST).enumerate() {
+            let source_text = batch
+                .iter()
+                .enumerate()
+                .map(|(i, term)| format!("{}: \"{}\"", i + 1, term.replace('"', "\\\"")))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let system_prompt = self.load_system_prompt(
+                TRANSLATE_TERMS_PROMPT,
+                source_lang,
+                target_lang,
+                &source_text,
+            )?;
+            let messages = vec![system_message(system_prompt), user_message(source_text)];
+
+            log::info!(
+                "Sending term translation request {}/{} with {} terms...",
+                batch_index + 1,
+                terms.len().div_ceil(TERMS_PER_REQUEST),
+                batch.len()
+            );
+            let response = self.api_client.chat_completions(messages).await?;
+            log::info!(
+                "Received term translation response, tokens used: {} + {} = {}",
+                response.usage.prompt_tokens,
+                response.usage.completion_tokens,
+                response.usage.total_tokens
+            );
+            let content = &response
+                .choices
+                .first()
+                .ok_or_else(|| {
+                    TranslationError::Translate(crate::error::TranslateError::InvalidResponse(
+                        "No choices in API response".to_string(),
+                    ))
+                })?
+                .message
+                .content;
+
+            let translated: HashMap<String, String> = parse_entries(content)
+                .into_iter()
+                .map(|e| (e.key, e.value.trim().to_string()))
+                .collect();
+            for (i, term) in batch.iter().enumerate() {
+                match translated.get(&(i + 1).to_string()) {
+                    Some(value) if !value.is_empty() => results.push((term.clone(), value.clone())),
+                    _ => log::warn!("No translation returned for term '{}'", term),
+                }
+            }
+        }
+        Ok(results)
+    }
 }
@@ -0,0 +1,142 @@
+//! 重复键验证
+//!
+//! 检查整个语言目录中同一个键是否在多个文件或同一文件中重复定义，并给出游戏实际使用的定义。
+//! 游戏按以下顺序取第一个读到的定义：`replace/` 目录下的文件优先于普通文件，
+//! 同类文件之间按文件名排序，同一文件中先出现的条目优先。
+
+use super::Problem;
+use crate::error::Result;
+use crate::preprocess::read_entries;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 键的一处定义
+#[derive(Debug, Clone)]
+struct KeyDefinition {
+    /// 相对语言目录的文件路径
+    file: PathBuf,
+    line: usize,
+    /// 是否位于 `replace/` 目录下
+    replace: bool,
+}
+
+impl KeyDefinition {
+    fn location(&self) -> String {
+        format!("{}:{}", self.file.display(), self.line)
+    }
+
+    /// 游戏读取该定义的先后顺序
+    fn load_order(&self) -> (bool, Option<&std::ffi::OsStr>) {
+        (!self.replace, self.file.file_name())
+    }
+}
+
+/// 查找语言目录中重复定义的键，按键名排序
+///
+/// `files` 为语言目录中属于该语言的文件，所有语言位于同一目录时不含其他语言的文件
+pub fn find_duplicate_keys(dir: &Path, files: &[PathBuf]) -> Result<Vec<Problem>> {
+    let mut definitions: HashMap<String, Vec<KeyDefinition>> = HashMap::new();
+    for file in files {
+        let relative = file.strip_prefix(dir).unwrap_or(file).to_path_buf();
+        let replace = relative
+            .parent()
+            .is_some_and(|parent| parent.components().any(|c| c.as_os_str() == "replace"));
+        for entry in read_entries(file)? {
+            definitions
+                .entry(entry.key)
+                .or_default()
+                .push(KeyDefinition {
+                    file: relative.clone(),
+                    line: entry.line,
+                    replace,
+                });
+        }
+    }
+
+    let mut problems: Vec<Problem> = definitions
+        .into_iter()
+        .filter(|(_, defs)| defs.len() > 1)
+        .map(|(key, mut defs)| {
+            defs.sort_by(|a, b| a.load_order().cmp(&b.load_order()));
+            Problem::DuplicateKey {
+                key,
+                used: defs[0].location(),
+                ignored: defs[1..].iter().map(KeyDefinition::location).collect(),
+            }
+        })
+        .collect();
+    problems.sort_by(|a, b| match (a, b) {
+        (Problem::DuplicateKey { key: a, .. }, Problem::DuplicateKey { key: b, .. }) => a.cmp(b),
+        _ => std::cmp::Ordering::Equal,
+    });
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_tree;
+
+    fn duplicates(files: &[(&str, &str)]) -> Vec<String> {
+        let dir = temp_tree(files);
+        let files = crate::preprocess::find_localisation_files(dir.path()).unwrap();
+        find_duplicate_keys(dir.path(), &files)
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_replace_folder_wins() {
+        let problems = duplicates(&[
+            ("a_events_l_english.yml", "l_english:\n a:0 \"A\"\n"),
+            ("replace/z_l_english.yml", "l_english:\n a:0 \"Z\"\n"),
+        ]);
+        let replace = Path::new("replace").join("z_l_english.yml");
+        assert_eq!(
+            problems,
+            [format!(
+                "Key 'a' is defined 2 times; the game uses {}:2, ignoring a_events_l_english.yml:2",
+                replace.display()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_files_load_in_name_order() {
+        let problems = duplicates(&[
+            ("b_events_l_english.yml", "l_english:\n a:0 \"B\"\n"),
+            ("a_events_l_english.yml", "l_english:\n a:0 \"A\"\n"),
+        ]);
+        assert_eq!(
+            problems,
+            [
+                "Key 'a' is defined 2 times; the game uses a_events_l_english.yml:2, ignoring b_events_l_english.yml:2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_definition_in_a_file_wins() {
+        let problems = duplicates(&[(
+            "events_l_english.yml",
+            "l_english:\n c:0 \"first\"\n c:0 \"second\"\n",
+        )]);
+        assert_eq!(
+            problems,
+            [
+                "Key 'c' is defined 2 times; the game uses events_l_english.yml:2, ignoring events_l_english.yml:3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unique_keys_are_not_reported() {
+        let problems = duplicates(&[
+            ("a_l_english.yml", "l_english:\n a:0 \"A\"\n"),
+            ("b_l_english.yml", "l_english:\n b:0 \"B\"\n"),
+        ]);
+        assert!(problems.is_empty());
+    }
+}
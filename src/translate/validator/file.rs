@@ -0,0 +1,137 @@
+//! 译文文件结构验证
+//!
+//! 游戏会静默忽略没有 UTF-8 BOM、语言头不是 `l_<语言>:` 或文件名不以 `_l_<语言>.yml` 结尾的文件。
+//! 检查目标语言目录中的每个文件，并报告没有对应源文件的多余文件。
+
+use super::Problem;
+use crate::error::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 检查语言目录中每个文件的 BOM、语言头与文件名
+///
+/// `files` 为语言目录中的文件，`output_paths` 为源文件对应的译文文件路径
+pub fn check_language_files(
+    dir: &Path,
+    files: &[PathBuf],
+    target_lang: &str,
+    output_paths: &[PathBuf],
+) -> Result<Vec<Problem>> {
+    let header = format!("l_{}:", target_lang);
+    let suffix = format!("_l_{}.yml", target_lang);
+    let expected: HashSet<&PathBuf> = output_paths.iter().collect();
+
+    let mut problems = Vec::new();
+    for path in files {
+        let file = path.strip_prefix(dir).unwrap_or(path).display().to_string();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let content = std::fs::read(path)?;
+
+        if !content.starts_with("\u{FEFF}".as_bytes()) {
+            problems.push(Problem::MissingBom { file: file.clone() });
+        }
+        let text = String::from_utf8_lossy(&content);
+        let found = text
+            .trim_start_matches('\u{FEFF}')
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|line| line.starts_with("l_") && line.ends_with(':'))
+            .map(str::to_string);
+        if found.as_deref() != Some(header.as_str()) {
+            problems.push(Problem::WrongHeader {
+                file: file.clone(),
+                expected: header.clone(),
+                found,
+            });
+        }
+        if !name.ends_with(&suffix) {
+            problems.push(Problem::WrongFilename {
+                file: file.clone(),
+                expected: suffix.clone(),
+            });
+        }
+        if !expected.contains(path) {
+            problems.push(Problem::StrayFile { file });
+        }
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_tree;
+
+    /// 检查单个译文文件，`output` 表示它是否有对应的源文件
+    fn check(name: &str, content: &str, output: bool) -> Vec<String> {
+        let dir = temp_tree(&[(name, content)]);
+        let path = dir.path().join(name);
+        let outputs = if output {
+            vec![path.clone()]
+        } else {
+            Vec::new()
+        };
+        check_language_files(dir.path(), &[path], "simp_chinese", &outputs)
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_accepts_well_formed_file() {
+        let content = "\u{FEFF}# 注释\nl_simp_chinese:\n a:0 \"甲\"\n";
+        assert!(check("events_l_simp_chinese.yml", content, true).is_empty());
+    }
+
+    #[test]
+    fn test_reports_missing_bom() {
+        let content = "l_simp_chinese:\n a:0 \"甲\"\n";
+        assert_eq!(
+            check("events_l_simp_chinese.yml", content, true),
+            ["File 'events_l_simp_chinese.yml' has no UTF-8 BOM"]
+        );
+    }
+
+    #[test]
+    fn test_reports_wrong_header() {
+        let content = "\u{FEFF}l_english:\n a:0 \"甲\"\n";
+        assert_eq!(
+            check("events_l_simp_chinese.yml", content, true),
+            [
+                "File 'events_l_simp_chinese.yml' starts with header 'l_english:' instead of 'l_simp_chinese:'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_missing_header() {
+        let content = "\u{FEFF} a:0 \"甲\"\n";
+        assert_eq!(
+            check("events_l_simp_chinese.yml", content, true),
+            ["File 'events_l_simp_chinese.yml' has no 'l_simp_chinese:' header"]
+        );
+    }
+
+    #[test]
+    fn test_reports_wrong_file_name() {
+        let content = "\u{FEFF}l_simp_chinese:\n a:0 \"甲\"\n";
+        assert_eq!(
+            check("events_l_simp_chinese.yaml", content, true),
+            ["File name of 'events_l_simp_chinese.yaml' does not end with '_l_simp_chinese.yml'"]
+        );
+    }
+
+    #[test]
+    fn test_reports_file_without_source() {
+        let content = "\u{FEFF}l_simp_chinese:\n a:0 \"甲\"\n";
+        assert_eq!(
+            check("old_events_l_simp_chinese.yml", content, false),
+            ["File 'old_events_l_simp_chinese.yml' has no corresponding source file"]
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! 术语一致性验证
+//!
+//! 检查源文本中出现的术语表术语，在译文中是否使用了术语表规定的译法。
+//! 图标、变量、命令等标记中的文本不参与匹配。
+
+use super::Problem;
+use crate::preprocess::paired_entries;
+use crate::translate::glossary::Glossary;
+use crate::translate::markup::MarkupSyntax;
+use regex::Regex;
+
+/// 单个术语的检查规则
+struct TermRule {
+    /// 源术语匹配模式
+    pattern: Regex,
+    /// 源术语
+    source: String,
+    /// 可接受的译法，首个为主译法
+    accepted: Vec<String>,
+    /// 禁止使用的译法
+    forbidden: Vec<String>,
+}
+
+/// 术语一致性验证器
+pub struct GlossaryValidator {
+    terms: Vec<TermRule>,
+    /// 游戏支持的标记语法，匹配前去除标记
+    markup: MarkupSyntax,
+}
+
+impl GlossaryValidator {
+    /// 从术语表中提取同时具有源语言和目标语言的术语
+    pub fn new(
+        glossary: &Glossary,
+        source_lang: &str,
+        target_lang: &str,
+        markup: MarkupSyntax,
+    ) -> Self {
+        let mut terms: Vec<TermRule> = glossary
+            .entries()
+            .values()
+            .filter_map(|item| {
+                let source = item.get(source_lang)?;
+                let target = item.get(target_lang)?;
+                if source.trim().is_empty() || target.trim().is_empty() {
+                    return None;
+                }
+                let mut accepted = vec![target.to_string()];
+                accepted.extend(item.alternatives(target_lang).iter().cloned());
+                // 按单词边界匹配，避免 ore 命中 more；默认不区分大小写
+                let flags = if item.case_sensitive { "" } else { "(?i)" };
+                let pattern = format!(r"{}\b{}\b", flags, regex::escape(source));
+                Regex::new(&pattern).ok().map(|pattern| TermRule {
+                    pattern,
+                    source: source.to_string(),
+                    accepted,
+                    forbidden: item.forbidden(target_lang).to_vec(),
+                })
+            })
+            .collect();
+        // 长术语优先，使其能够覆盖其中包含的短术语
+        terms.sort_by(|a, b| {
+            b.source
+                .len()
+                .cmp(&a.source.len())
+                .then_with(|| a.source.cmp(&b.source))
+        });
+        Self { terms, markup }
+    }
+
+    /// 术语表中是否没有可用的术语
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// 验证一个切片的译文是否遵守术语表
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.terms.is_empty() {
+            return problems;
+        }
+        for (key, original, translated) in paired_entries(original, translated) {
+            self.validate_entry(key, original, translated, &mut problems);
+        }
+        problems
+    }
+
+    /// 验证单个条目，返回新增的问题数
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let mut problems_added = 0;
+        let original_plain = self.markup.plain_text(original);
+        let translated_lower = self.markup.plain_text(translated).to_lowercase();
+        // 已被较长术语覆盖的源文本区间
+        let mut covered: Vec<(usize, usize)> = Vec::new();
+        for rule in &self.terms {
+            let mut matched = false;
+            for m in rule.pattern.find_iter(&original_plain) {
+                let inside = covered
+                    .iter()
+                    .any(|(start, end)| m.start() >= *start && m.end() <= *end);
+                if !inside {
+                    covered.push((m.start(), m.end()));
+                    matched = true;
+                }
+            }
+            if !matched {
+                continue;
+            }
+            let adhered = rule
+                .accepted
+                .iter()
+                .any(|target| translated_lower.contains(&target.to_lowercase()));
+            if !adhered {
+                problems.push(Problem::GlossaryViolation {
+                    key: key.to_string(),
+                    term: rule.source.clone(),
+                    expected: rule.accepted.join(" / "),
+                    found: translated.to_string(),
+                });
+                problems_added += 1;
+            }
+            // 去掉可接受的译法后再查找禁止译法，避免禁止译法是可接受译法一部分时误报
+            let mut remaining = translated_lower.clone();
+            for target in &rule.accepted {
+                remaining = remaining.replace(&target.to_lowercase(), " ");
+            }
+            for forbidden in &rule.forbidden {
+                if remaining.contains(&forbidden.to_lowercase()) {
+                    problems.push(Problem::GlossaryForbidden {
+                        key: key.to_string(),
+                        term: rule.source.clone(),
+                        forbidden: forbidden.clone(),
+                        found: translated.to_string(),
+                    });
+                    problems_added += 1;
+                }
+            }
+        }
+        problems_added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translate::glossary::GlossaryItem;
+
+    fn glossary() -> Glossary {
+        let mut glossary = Glossary::default();
+        for (en, zh) in [
+            ("research", "研究"),
+            ("research lab", "研究实验室"),
+            ("ore", "矿石"),
+        ] {
+            let mut item = GlossaryItem::default();
+            item.set("english", Some(en.to_string())).unwrap();
+            item.set("simp_chinese", Some(zh.to_string())).unwrap();
+            glossary.insert(en.to_string(), item).unwrap();
+        }
+        glossary
+    }
+
+    #[test]
+    fn test_glossary_violation() {
+        let validator = GlossaryValidator::new(
+            &glossary(),
+            "english",
+            "simp_chinese",
+            MarkupSyntax::default(),
+        );
+        let original =
+            "a: \"Build a Research Lab\"\nb: \"We need more power\"\nc: \"Mine the ore\"";
+        let translated = "a: \"建造一座科研实验室\"\nb: \"我们需要更多电力\"\nc: \"开采矿石\"";
+        let problems = validator.validate(original, translated);
+        assert_eq!(problems.len(), 1);
+        match &problems[0] {
+            Problem::GlossaryViolation {
+                key,
+                term,
+                expected,
+                found,
+            } => {
+                assert_eq!(key, "a");
+                assert_eq!(term, "research lab");
+                assert_eq!(expected, "研究实验室");
+                assert_eq!(found, "建造一座科研实验室");
+            }
+            other => panic!("unexpected problem: {}", other),
+        }
+    }
+
+    /// 按扩展规则的词汇表检查，返回每个问题的键名与类别
+    fn extended_problems(original: &str, translated: &str) -> Vec<(String, &'static str)> {
+        let json = r#"{
+            "energy": {"1": "energy", "2": {"term": "能量", "alternatives": ["能源"], "forbidden": ["精力"]}},
+            "us": {"1": "US", "2": "美国", "case_sensitive": true}
+        }"#;
+        let path = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(path.path(), json).unwrap();
+        let glossary = Glossary::from_json_file(path.path()).unwrap();
+        GlossaryValidator::new(
+            &glossary,
+            "english",
+            "simp_chinese",
+            MarkupSyntax::default(),
+        )
+        .validate(original, translated)
+        .into_iter()
+        .map(|problem| match problem {
+            Problem::GlossaryViolation { key, .. } => (key, "violation"),
+            Problem::GlossaryForbidden { key, .. } => (key, "forbidden"),
+            other => panic!("unexpected problem: {}", other),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_glossary_accepts_alternatives() {
+        assert!(extended_problems("a: \"Energy output\"", "a: \"能源产出\"").is_empty());
+    }
+
+    #[test]
+    fn test_glossary_reports_forbidden_translation() {
+        assert_eq!(
+            extended_problems("b: \"Energy boost\"", "b: \"精力提升\""),
+            [
+                ("b".to_string(), "violation"),
+                ("b".to_string(), "forbidden")
+            ]
+        );
+        assert_eq!(
+            extended_problems("d: \"Energy\"", "d: \"能量与精力\""),
+            [("d".to_string(), "forbidden")]
+        );
+    }
+
+    #[test]
+    fn test_glossary_case_sensitive_term() {
+        assert!(extended_problems("c: \"Tell us more\"", "c: \"告诉我们更多\"").is_empty());
+        assert_eq!(
+            extended_problems("e: \"Made in the US\"", "e: \"合众国制造\""),
+            [("e".to_string(), "violation")]
+        );
+    }
+
+    #[test]
+    fn test_glossary_ignores_markup() {
+        let validator = GlossaryValidator::new(
+            &glossary(),
+            "english",
+            "simp_chinese",
+            MarkupSyntax::default(),
+        );
+        let original = "a: \"£ore£ $research$ [Root.GetOre]\"\nb: \"§Yore§!\"";
+        let translated = "a: \"£ore£ $research$ [Root.GetOre]\"\nb: \"§Y矿物§!\"";
+        let problems = validator.validate(original, translated);
+        assert_eq!(problems.len(), 1);
+        assert!(matches!(&problems[0], Problem::GlossaryViolation { key, .. } if key == "b"));
+    }
+}
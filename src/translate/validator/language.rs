@@ -0,0 +1,218 @@
+//! 目标语言验证
+//!
+//! 检查译文是否与原文完全相同（未翻译），以及译文的文字是否符合目标语言，
+//! 如简体中文的译文大部分是拉丁字母、俄语的译文没有西里尔字母。
+//! 文字按 Unicode 书写系统（script）判断，标记与禁止翻译的文本不计入。
+
+use super::Problem;
+use crate::config::ValidationSettings;
+use crate::preprocess::paired_entries;
+use crate::translate::do_not_translate::DoNotTranslate;
+use crate::translate::markup::MarkupSyntax;
+use regex::Regex;
+
+/// 字母数少于该值的文本不判断书写系统
+const MIN_LETTERS: usize = 4;
+
+/// 目标语言使用的书写系统：(名称, 匹配模式)
+fn expected_script(lang: &str) -> Option<(&'static str, &'static str)> {
+    let script = match lang {
+        "simp_chinese" => ("Han", r"\p{Han}"),
+        "japanese" => ("Han/Kana", r"[\p{Han}\p{Hiragana}\p{Katakana}]"),
+        "korean" => ("Hangul", r"[\p{Hangul}\p{Han}]"),
+        "russian" => ("Cyrillic", r"\p{Cyrillic}"),
+        "english" | "french" | "german" | "spanish" | "braz_por" | "polish" | "turkish" => {
+            ("Latin", r"\p{Latin}")
+        }
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// 目标语言验证器
+pub struct LanguageValidator {
+    source_lang: String,
+    target_lang: String,
+    /// 目标语言的书写系统：(名称, 匹配该书写系统字母的模式)
+    script: Option<(&'static str, Regex)>,
+    /// 匹配任意字母
+    letter_pattern: Regex,
+    markup: MarkupSyntax,
+    do_not_translate: DoNotTranslate,
+    settings: ValidationSettings,
+}
+
+impl LanguageValidator {
+    pub fn new(
+        source_lang: &str,
+        target_lang: &str,
+        markup: MarkupSyntax,
+        do_not_translate: &DoNotTranslate,
+        settings: &ValidationSettings,
+    ) -> Self {
+        Self {
+            source_lang: source_lang.to_string(),
+            target_lang: target_lang.to_string(),
+            script: expected_script(target_lang)
+                .map(|(name, pattern)| (name, Regex::new(pattern).unwrap())),
+            letter_pattern: Regex::new(r"\p{L}").unwrap(),
+            markup,
+            do_not_translate: do_not_translate.clone(),
+            settings: settings.clone(),
+        }
+    }
+
+    /// 验证一个切片的译文是否为目标语言
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.source_lang == self.target_lang {
+            return problems;
+        }
+        for (key, original, translated) in paired_entries(original, translated) {
+            self.validate_entry(key, original, translated, &mut problems);
+        }
+        problems
+    }
+
+    /// 验证单个条目，返回新增的问题数
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let plain = self.plain_text(translated);
+        let letters = self.letter_pattern.find_iter(&plain).count();
+        // 纯标记、纯数字的文本无需翻译
+        if letters == 0 {
+            return 0;
+        }
+
+        if original.trim() == translated.trim() {
+            let text = translated.trim();
+            let allowed = self
+                .settings
+                .allow_untranslated
+                .iter()
+                .any(|allowed| allowed == text || allowed == plain.trim())
+                || self.do_not_translate.is_protected(text);
+            if allowed {
+                return 0;
+            }
+            problems.push(Problem::Untranslated {
+                key: key.to_string(),
+                text: text.to_string(),
+            });
+            return 1;
+        }
+
+        let Some((script_name, script)) = &self.script else {
+            return 0;
+        };
+        if letters < MIN_LETTERS {
+            return 0;
+        }
+        let matched = script.find_iter(&plain).count();
+        let ratio = matched as f64 / letters as f64;
+        if ratio < self.settings.min_script_ratio {
+            problems.push(Problem::WrongScript {
+                key: key.to_string(),
+                lang: self.target_lang.clone(),
+                script: script_name.to_string(),
+                ratio,
+            });
+            return 1;
+        }
+        0
+    }
+
+    /// 去除标记与禁止翻译的文本后的纯文本
+    fn plain_text(&self, value: &str) -> String {
+        let plain = self.markup.plain_text(value);
+        let mut result = String::with_capacity(plain.len());
+        let mut last = 0;
+        for (start, end) in self.do_not_translate.find(&plain) {
+            result.push_str(&plain[last..start]);
+            result.push(' ');
+            last = end;
+        }
+        result.push_str(&plain[last..]);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(target_lang: &str, original: &str, translated: &str) -> Vec<String> {
+        let settings = ValidationSettings {
+            allow_untranslated: vec!["DLC".to_string()],
+            ..Default::default()
+        };
+        let dnt = DoNotTranslate::from_entries(&["Fungal Sanctuary"]).unwrap();
+        LanguageValidator::new(
+            "english",
+            target_lang,
+            MarkupSyntax::STELLARIS,
+            &dnt,
+            &settings,
+        )
+        .validate(original, translated)
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_reports_untranslated_value() {
+        assert_eq!(
+            problems(
+                "simp_chinese",
+                "a: \"Energy Credits\"",
+                "a: \"Energy Credits\""
+            ),
+            ["Key 'a' is identical to the source text: 'Energy Credits'"]
+        );
+    }
+
+    #[test]
+    fn test_allows_configured_untranslated_value() {
+        assert!(problems("simp_chinese", "b: \"DLC\"", "b: \"DLC\"").is_empty());
+    }
+
+    #[test]
+    fn test_ignores_markup_only_value() {
+        let value = "c: \"$VALUE$ £energy£\"";
+        assert!(problems("simp_chinese", value, value).is_empty());
+    }
+
+    #[test]
+    fn test_reports_wrong_script() {
+        assert_eq!(
+            problems(
+                "simp_chinese",
+                "d: \"The fleet has arrived at the planet\"",
+                "d: \"The fleet 已经 arrived at the planet\""
+            ),
+            ["Key 'd' does not look like simp_chinese: only 7% of its letters are Han"]
+        );
+    }
+
+    #[test]
+    fn test_ignores_do_not_translate_terms() {
+        let original = "e: \"Visit the Fungal Sanctuary today\"\nf: \"Fungal Sanctuary\"";
+        let translated = "e: \"今天就去 Fungal Sanctuary\"\nf: \"Fungal Sanctuary\"";
+        assert!(problems("simp_chinese", original, translated).is_empty());
+    }
+
+    #[test]
+    fn test_checks_cyrillic_script() {
+        assert!(problems("russian", "a: \"Energy\"", "a: \"Энергия\"").is_empty());
+        assert_eq!(
+            problems("russian", "a: \"Energy\"", "a: \"Energia\"").len(),
+            1
+        );
+    }
+}
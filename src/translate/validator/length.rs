@@ -0,0 +1,174 @@
+//! 译文长度验证
+//!
+//! 按目标语言计算译文与原文的期望长度比，找出明显偏离的条目：
+//! 过短的译文可能被截断或漏译，过长的译文可能混入了大模型编造的内容。
+//! 长度为去除标记后非空白字符的个数。
+
+use super::Problem;
+use crate::config::ValidationSettings;
+use crate::preprocess::paired_entries;
+use crate::translate::markup::MarkupSyntax;
+
+/// 各语言文本相对英语的典型长度（去除空白后的字符数之比）
+fn length_factor(lang: &str) -> f64 {
+    match lang {
+        "simp_chinese" => 0.32,
+        "japanese" => 0.45,
+        "korean" => 0.45,
+        "russian" | "polish" => 1.05,
+        "french" | "german" => 1.15,
+        "spanish" | "braz_por" => 1.1,
+        _ => 1.0,
+    }
+}
+
+/// 译文长度验证器
+pub struct LengthValidator {
+    /// 期望的译文与原文长度比，源语言与目标语言相同时为 None
+    expected: Option<f64>,
+    markup: MarkupSyntax,
+    /// 允许偏离期望长度比的倍数
+    tolerance: f64,
+    /// 原文短于该长度时不检查
+    min_length: usize,
+}
+
+impl LengthValidator {
+    pub fn new(
+        source_lang: &str,
+        target_lang: &str,
+        markup: MarkupSyntax,
+        settings: &ValidationSettings,
+    ) -> Self {
+        let expected = (source_lang != target_lang).then(|| {
+            settings
+                .length_ratios
+                .get(target_lang)
+                .copied()
+                .unwrap_or_else(|| length_factor(target_lang) / length_factor(source_lang))
+        });
+        Self {
+            expected,
+            markup,
+            tolerance: settings.length_tolerance,
+            min_length: settings.min_length,
+        }
+    }
+
+    /// 验证一个切片的译文长度
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (key, original, translated) in paired_entries(original, translated) {
+            self.validate_entry(key, original, translated, &mut problems);
+        }
+        problems
+    }
+
+    /// 验证单个条目，返回新增的问题数
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let Some(expected) = self.expected else {
+            return 0;
+        };
+        let original_length = self.length(original);
+        if original_length < self.min_length {
+            return 0;
+        }
+        let ratio = self.length(translated) as f64 / original_length as f64;
+        if ratio > expected * self.tolerance || ratio < expected / self.tolerance {
+            problems.push(Problem::LengthAnomaly {
+                key: key.to_string(),
+                ratio,
+                expected,
+            });
+            return 1;
+        }
+        0
+    }
+
+    /// 去除标记后非空白字符的个数
+    fn length(&self, value: &str) -> usize {
+        self.markup
+            .plain_text(value)
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const ORIGINAL: &str = "The fleet has arrived at the frontier and awaits orders";
+
+    fn anomalies(settings: &ValidationSettings, original: &str, translated: &str) -> Vec<String> {
+        LengthValidator::new("english", "simp_chinese", MarkupSyntax::STELLARIS, settings)
+            .validate(original, translated)
+            .into_iter()
+            .map(|p| match p {
+                Problem::LengthAnomaly { key, .. } => key,
+                other => panic!("unexpected problem: {}", other),
+            })
+            .collect()
+    }
+
+    fn check(original: &str, translated: &str) -> Vec<String> {
+        anomalies(
+            &ValidationSettings::default(),
+            &format!("a: \"{}\"", original),
+            &format!("a: \"{}\"", translated),
+        )
+    }
+
+    #[test]
+    fn test_accepts_expected_length() {
+        assert!(check(ORIGINAL, "舰队已抵达边境，等待命令").is_empty());
+    }
+
+    #[test]
+    fn test_reports_too_short_translation() {
+        assert_eq!(check(ORIGINAL, "舰队"), ["a"]);
+    }
+
+    #[test]
+    fn test_reports_too_long_translation() {
+        let translated = "舰队已抵达边境，等待命令。".repeat(3);
+        assert_eq!(check(ORIGINAL, &translated), ["a"]);
+    }
+
+    #[test]
+    fn test_ignores_markup() {
+        assert!(
+            check(
+                "§YThe fleet§! has arrived at $PLANET$ and awaits orders",
+                "§Y舰队§!已抵达$PLANET$，等待命令"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_ignores_short_values() {
+        assert!(check("Short", "短短短短短短短短短短短短短短短短").is_empty());
+    }
+
+    #[test]
+    fn test_custom_length_ratio() {
+        let settings = ValidationSettings {
+            length_ratios: HashMap::from([("simp_chinese".to_string(), 0.8)]),
+            ..Default::default()
+        };
+        let original = format!("a: \"{}\"", ORIGINAL);
+        assert_eq!(
+            anomalies(&settings, &original, "a: \"舰队已抵达边境，等待命令\""),
+            ["a"]
+        );
+    }
+}
@@ -0,0 +1,577 @@
+//! 验证器模块
+//!
+//! 验证翻译后的文本是否破坏了游戏特殊格式与条目结构，是否遵守术语表，是否保留了禁止翻译的文本，
+//! 以及是否确实翻译成了目标语言。
+
+mod duplicate;
+mod file;
+mod glossary;
+mod language;
+mod length;
+mod number;
+mod protected;
+mod structure;
+
+pub use duplicate::*;
+pub use file::*;
+pub use glossary::*;
+pub use language::*;
+pub use length::*;
+pub use number::*;
+pub use protected::*;
+pub use structure::*;
+
+use std::{collections::HashSet, fmt::Display};
+
+use crate::config::ValidationSettings;
+use crate::preprocess::{paired_entries, parse_entries};
+use crate::translate::do_not_translate::DoNotTranslate;
+use crate::translate::glossary::Glossary;
+use crate::translate::markup::{MarkupKind, MarkupSyntax};
+
+/// 特殊格式验证器
+///
+/// 将文本切分为标记后比较：颜色代码、参数、图标、命令与概念命令的 key 必须原样保留，
+/// 颜色 `§X…§!`、文本格式 `#format … #!` 与命令 `[…]` 的配对不能比原文更差
+#[derive(Debug, Default)]
+pub struct FormatValidator {
+    /// 游戏支持的标记语法
+    markup: MarkupSyntax,
+}
+
+#[derive(Debug)]
+pub enum Problem {
+    /// 键缺失
+    MissingKey { key: String },
+    /// 额外的键
+    ExtraKey { key: String },
+    /// 标记未找到
+    PatternNotFound { key: String, original: String },
+    /// 标记内容被改变
+    PatternMismatch {
+        key: String,
+        original: String,
+        translated: String,
+    },
+    /// 源文本包含术语，但译文未使用术语表规定的译法
+    GlossaryViolation {
+        key: String,
+        term: String,
+        expected: String,
+        found: String,
+    },
+    /// 译文使用了术语表禁止的译法
+    GlossaryForbidden {
+        key: String,
+        term: String,
+        forbidden: String,
+        found: String,
+    },
+    /// 禁止翻译的文本在译文中被改动或丢失
+    ProtectedTermChanged {
+        key: String,
+        term: String,
+        found: String,
+    },
+    /// 译文中出现了原文没有的标记
+    UnexpectedPattern { key: String, translated: String },
+    /// 译文中颜色或命令的配对被破坏
+    UnbalancedMarkup { key: String, issue: String },
+    /// 译文的 `\n` 转义个数与原文不同
+    NewlineCountMismatch {
+        key: String,
+        original: usize,
+        translated: usize,
+    },
+    /// 译文的值中含有未转义的双引号
+    UnescapedQuote { key: String },
+    /// 译文的值跨越多行
+    MultilineValue { key: String },
+    /// 译文的值中含有不可见字符
+    NonPrintableChar { key: String, character: char },
+    /// 译文的值首尾多出了空白
+    EdgeWhitespace { key: String },
+    /// 译文与原文完全相同
+    Untranslated { key: String, text: String },
+    /// 译文中属于目标语言文字的字母比例过低
+    WrongScript {
+        key: String,
+        lang: String,
+        script: String,
+        ratio: f64,
+    },
+    /// 译文与原文的长度比明显偏离该目标语言的期望值
+    LengthAnomaly {
+        key: String,
+        ratio: f64,
+        expected: f64,
+    },
+    /// 同一个键在语言目录中定义了多次
+    DuplicateKey {
+        key: String,
+        /// 游戏实际使用的定义位置
+        used: String,
+        /// 被忽略的定义位置
+        ignored: Vec<String>,
+    },
+    /// 原文中的数字在译文中缺失
+    NumberNotFound { key: String, number: String },
+    /// 译文中出现了原文没有的数字
+    UnexpectedNumber { key: String, number: String },
+    /// 数字被改变
+    NumberMismatch {
+        key: String,
+        original: String,
+        translated: String,
+    },
+    /// 文件没有 UTF-8 BOM
+    MissingBom { file: String },
+    /// 文件的语言头与所在的语言目录不符
+    WrongHeader {
+        file: String,
+        expected: String,
+        found: Option<String>,
+    },
+    /// 文件名不以 `_l_<语言>.yml` 结尾
+    WrongFilename { file: String, expected: String },
+    /// 目标语言目录中没有对应源文件的文件
+    StrayFile { file: String },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingKey { key } => write!(f, "Missing key '{}'", key),
+            Problem::ExtraKey { key } => write!(f, "Extra key '{}'", key),
+            Problem::PatternNotFound { key, original } => {
+                write!(f, "Pattern not found for key '{}' in '{}'", key, original)
+            }
+            Problem::PatternMismatch {
+                key,
+                original,
+                translated,
+            } => write!(
+                f,
+                "Pattern mismatch for key '{}': '{}' => '{}'",
+                key, original, translated
+            ),
+            Problem::GlossaryViolation {
+                key,
+                term,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Glossary term '{}' for key '{}' should be translated as '{}', found '{}'",
+                term, key, expected, found
+            ),
+            Problem::GlossaryForbidden {
+                key,
+                term,
+                forbidden,
+                found,
+            } => write!(
+                f,
+                "Glossary term '{}' for key '{}' must not be translated as '{}', found '{}'",
+                term, key, forbidden, found
+            ),
+            Problem::ProtectedTermChanged { key, term, found } => write!(
+                f,
+                "Protected text '{}' for key '{}' must be kept verbatim, found '{}'",
+                term, key, found
+            ),
+            Problem::UnexpectedPattern { key, translated } => write!(
+                f,
+                "Unexpected pattern for key '{}': '{}' is not in the original",
+                key, translated
+            ),
+            Problem::UnbalancedMarkup { key, issue } => {
+                write!(f, "Unbalanced markup for key '{}': {}", key, issue)
+            }
+            Problem::NewlineCountMismatch {
+                key,
+                original,
+                translated,
+            } => write!(
+                f,
+                "Key '{}' has {} '\\n' escapes in the original but {} in the translation",
+                key, original, translated
+            ),
+            Problem::UnescapedQuote { key } => {
+                write!(f, "Key '{}' contains unescaped double quotes", key)
+            }
+            Problem::MultilineValue { key } => {
+                write!(f, "Value of key '{}' spans multiple lines", key)
+            }
+            Problem::NonPrintableChar { key, character } => write!(
+                f,
+                "Key '{}' contains non-printable character U+{:04X}",
+                key, *character as u32
+            ),
+            Problem::EdgeWhitespace { key } => write!(
+                f,
+                "Key '{}' has leading or trailing whitespace not in the original",
+                key
+            ),
+            Problem::Untranslated { key, text } => write!(
+                f,
+                "Key '{}' is identical to the source text: '{}'",
+                key, text
+            ),
+            Problem::WrongScript {
+                key,
+                lang,
+                script,
+                ratio,
+            } => write!(
+                f,
+                "Key '{}' does not look like {}: only {:.0}% of its letters are {}",
+                key,
+                lang,
+                ratio * 100.0,
+                script
+            ),
+            Problem::LengthAnomaly {
+                key,
+                ratio,
+                expected,
+            } => write!(
+                f,
+                "Key '{}' is {:.0}% of the source length, expected about {:.0}%",
+                key,
+                ratio * 100.0,
+                expected * 100.0
+            ),
+            Problem::DuplicateKey { key, used, ignored } => write!(
+                f,
+                "Key '{}' is defined {} times; the game uses {}, ignoring {}",
+                key,
+                ignored.len() + 1,
+                used,
+                ignored.join(", ")
+            ),
+            Problem::NumberNotFound { key, number } => write!(
+                f,
+                "Number '{}' of key '{}' is missing in the translation",
+                number, key
+            ),
+            Problem::UnexpectedNumber { key, number } => write!(
+                f,
+                "Number '{}' of key '{}' is not in the original",
+                number, key
+            ),
+            Problem::NumberMismatch {
+                key,
+                original,
+                translated,
+            } => write!(
+                f,
+                "Number '{}' of key '{}' was changed to '{}'",
+                original, key, translated
+            ),
+            Problem::MissingBom { file } => write!(f, "File '{}' has no UTF-8 BOM", file),
+            Problem::WrongHeader {
+                file,
+                expected,
+                found: Some(found),
+            } => write!(
+                f,
+                "File '{}' starts with header '{}' instead of '{}'",
+                file, found, expected
+            ),
+            Problem::WrongHeader {
+                file,
+                expected,
+                found: None,
+            } => write!(f, "File '{}' has no '{}' header", file, expected),
+            Problem::WrongFilename { file, expected } => write!(
+                f,
+                "File name of '{}' does not end with '{}'",
+                file, expected
+            ),
+            Problem::StrayFile { file } => {
+                write!(f, "File '{}' has no corresponding source file", file)
+            }
+        }
+    }
+}
+
+impl FormatValidator {
+    /// 创建新的验证器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按指定游戏的标记语法验证
+    pub fn with_markup(markup: MarkupSyntax) -> Self {
+        Self { markup }
+    }
+
+    /// 验证器使用的标记语法
+    pub fn markup(&self) -> MarkupSyntax {
+        self.markup
+    }
+
+    /// 验证翻译前后的格式是否一致
+    /// 传入的文本为一个切片的完整内容
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        self.validate_keys(original, translated, &mut problems);
+        for (key, original, translated) in paired_entries(original, translated) {
+            self.validate_entry(key, original, translated, &mut problems);
+        }
+        problems
+    }
+
+    /// 验证翻译前后条目的数量是否一致
+    fn validate_keys(
+        &self,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let mut problems_added = 0;
+        let original_entries = parse_entries(original);
+        let translated_entries = parse_entries(translated);
+        let original_keys: HashSet<&str> =
+            original_entries.iter().map(|e| e.key.as_str()).collect();
+        let translated_keys: HashSet<&str> =
+            translated_entries.iter().map(|e| e.key.as_str()).collect();
+        let missing = original_keys.difference(&translated_keys);
+        if missing.count() > 0 {
+            for key in original_keys.difference(&translated_keys) {
+                problems.push(Problem::MissingKey {
+                    key: key.to_string(),
+                });
+                problems_added += 1;
+            }
+        }
+        let extra = translated_keys.difference(&original_keys);
+        if extra.count() > 0 {
+            for key in translated_keys.difference(&original_keys) {
+                problems.push(Problem::ExtraKey {
+                    key: key.to_string(),
+                });
+                problems_added += 1;
+            }
+        }
+        problems_added
+    }
+
+    /// 验证特定键的格式标记是否一致
+    ///
+    /// 同类标记按多重集合比较，允许译文调整标记的顺序；
+    /// 原文与译文各自多出的标记按出现顺序配对报告为内容被改变，其余报告为缺失或多余
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let mut problems_added = 0;
+        let original_items = self.markup.items(original);
+        let translated_items = self.markup.items(translated);
+        for kind in [
+            MarkupKind::Icon,
+            MarkupKind::Variable,
+            MarkupKind::Color,
+            MarkupKind::Command,
+            MarkupKind::Concept,
+            MarkupKind::Format,
+            MarkupKind::TextIcon,
+        ] {
+            let mut missing: Vec<&str> = original_items
+                .iter()
+                .filter(|(k, _)| *k == kind)
+                .map(|(_, s)| s.as_str())
+                .collect();
+            let mut extra: Vec<&str> = Vec::new();
+            for (_, item) in translated_items.iter().filter(|(k, _)| *k == kind) {
+                match missing.iter().position(|it| it == item) {
+                    Some(i) => {
+                        missing.remove(i);
+                    }
+                    None => extra.push(item),
+                }
+            }
+            for (i, it_original) in missing.iter().enumerate() {
+                match extra.get(i) {
+                    Some(it_translated) => problems.push(Problem::PatternMismatch {
+                        key: key.to_string(),
+                        original: it_original.to_string(),
+                        translated: it_translated.to_string(),
+                    }),
+                    None => problems.push(Problem::PatternNotFound {
+                        key: key.to_string(),
+                        original: it_original.to_string(),
+                    }),
+                }
+                problems_added += 1;
+            }
+            for it_translated in extra.iter().skip(missing.len()) {
+                problems.push(Problem::UnexpectedPattern {
+                    key: key.to_string(),
+                    translated: it_translated.to_string(),
+                });
+                problems_added += 1;
+            }
+        }
+
+        // 原文本身就存在的配对问题（如故意不闭合的颜色）不重复报告
+        let mut original_issues = self.markup.issues(original);
+        for issue in self.markup.issues(translated) {
+            match original_issues.iter().position(|it| *it == issue) {
+                Some(i) => {
+                    original_issues.remove(i);
+                }
+                None => {
+                    problems.push(Problem::UnbalancedMarkup {
+                        key: key.to_string(),
+                        issue: issue.to_string(),
+                    });
+                    problems_added += 1;
+                }
+            }
+        }
+        problems_added
+    }
+
+    /// 提取所有特殊标记
+    pub fn extract_markers(&self, text: &str) -> Vec<String> {
+        self.markup
+            .items(text)
+            .into_iter()
+            .map(|(_, s)| s)
+            .collect()
+    }
+}
+
+/// 一组目标语言的全部验证器
+pub struct Validators {
+    pub format: FormatValidator,
+    pub structure: StructureValidator,
+    pub glossary: GlossaryValidator,
+    pub protected: ProtectedTermValidator,
+    pub language: LanguageValidator,
+    pub number: NumberValidator,
+    pub length: LengthValidator,
+}
+
+impl Validators {
+    /// 按游戏的标记语法与任务的验证设置创建某一目标语言的验证器
+    pub fn new(
+        glossary: &Glossary,
+        do_not_translate: &DoNotTranslate,
+        markup: MarkupSyntax,
+        settings: &ValidationSettings,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Self {
+        Self {
+            format: FormatValidator::with_markup(markup),
+            structure: StructureValidator::new(),
+            glossary: GlossaryValidator::new(glossary, source_lang, target_lang, markup),
+            protected: ProtectedTermValidator::new(do_not_translate),
+            language: LanguageValidator::new(
+                source_lang,
+                target_lang,
+                markup,
+                do_not_translate,
+                settings,
+            ),
+            number: NumberValidator::new(source_lang, target_lang, markup, settings),
+            length: LengthValidator::new(source_lang, target_lang, markup, settings),
+        }
+    }
+
+    /// 依次检查格式、条目结构、术语、禁止翻译的文本、目标语言、数字与译文长度
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = self.format.validate(original, translated);
+        problems.extend(self.structure.validate(original, translated));
+        problems.extend(self.glossary.validate(original, translated));
+        problems.extend(self.protected.validate(original, translated));
+        problems.extend(self.language.validate(original, translated));
+        problems.extend(self.number.validate(original, translated));
+        problems.extend(self.length.validate(original, translated));
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str =
+        r#"key:0 "§Y$target|Y$§! costs $5 £energy£ [Root.GetName] ['pop_growth', §G+10%§!]""#;
+
+    fn problems(translated: &str) -> Vec<String> {
+        FormatValidator::new()
+            .validate(ORIGINAL, translated)
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_accepts_reordered_patterns() {
+        let reordered =
+            r#"key:0 "[Root.GetName]花费 $5 £energy£ §Y$target|Y$§! ['pop_growth', §G+10%§!]""#;
+        assert!(problems(reordered).is_empty());
+    }
+
+    #[test]
+    fn test_reports_changed_pattern() {
+        let changed =
+            r#"key:0 "§Y$target|Y$§! 花费 $5 £energy|2£ [Root.GetName] ['pop_growth', §G+10%§!]""#;
+        assert_eq!(
+            problems(changed),
+            ["Pattern mismatch for key 'key': '£energy£' => '£energy|2£'"]
+        );
+    }
+
+    #[test]
+    fn test_reports_unexpected_pattern() {
+        let added =
+            r#"key:0 "§Y$target|Y$§! 花费 $5$ £energy£ [Root.GetName] ['pop_growth', §G+10%§!]""#;
+        assert_eq!(
+            problems(added),
+            ["Unexpected pattern for key 'key': '$5$' is not in the original"]
+        );
+    }
+
+    #[test]
+    fn test_reports_unclosed_bracket() {
+        let unclosed =
+            r#"key:0 "§Y$target|Y$§! 花费 $5 £energy£ [Root.GetName ['pop_growth', §G+10%§!]""#;
+        assert_eq!(
+            problems(unclosed),
+            [
+                "Pattern not found for key 'key' in '[Root.GetName]'",
+                "Unbalanced markup for key 'key': '[' is not closed by ']'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_unclosed_colour() {
+        let unclosed =
+            r#"key:0 "§Y$target|Y$ 花费 $5 £energy£ [Root.GetName] ['pop_growth', §G+10%§!]""#;
+        assert_eq!(
+            problems(unclosed),
+            ["Unbalanced markup for key 'key': colour '§Y' is not closed by '§!'"]
+        );
+    }
+
+    #[test]
+    fn test_ignores_comments() {
+        let original = " # £note£\n key:0 \"a:b £energy£\" # £trailing£\n";
+        let translated = " key:0 \"甲:乙 £energy£\" # 注释\n";
+        assert!(
+            FormatValidator::new()
+                .validate(original, translated)
+                .is_empty()
+        );
+    }
+}
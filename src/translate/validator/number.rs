@@ -0,0 +1,240 @@
+//! 数字一致性验证
+//!
+//! 提示文本中的数字（`+10%`、`per 100`、`3 years`）必须在译文中原样出现，
+//! 大模型有时会漏掉数字或改写为中文数字。比较原文与译文中标记之外的数字，
+//! 报告缺失、多余或被改变的数字。
+//! 数字按所在语言的小数点解析后比较，因此 `1,5`（德语）与 `1.5` 视为相同；
+//! 分隔符后恰好跟三位数字时才视为千位分隔符（`1,000`、`1 000`），`1,2,3` 这样的列表仍是三个数字；
+//! 全角数字可以视为对应的半角数字。
+
+use super::Problem;
+use crate::config::ValidationSettings;
+use crate::preprocess::paired_entries;
+use crate::translate::markup::MarkupSyntax;
+use regex::Regex;
+
+/// 文本中的一个数字
+struct NumberToken {
+    /// 原文中的写法
+    raw: String,
+    /// 用于比较的值
+    value: String,
+}
+
+/// 数字一致性验证器
+pub struct NumberValidator {
+    /// 原文是否以逗号作为小数点
+    source_decimal_comma: bool,
+    /// 译文是否以逗号作为小数点
+    target_decimal_comma: bool,
+    /// 是否将全角数字视为半角数字
+    fullwidth_digits: bool,
+    markup: MarkupSyntax,
+    /// 匹配原文中的数字，可以带千位分隔符或小数点
+    source_pattern: Regex,
+    /// 匹配译文中的数字
+    target_pattern: Regex,
+}
+
+/// 匹配数字的正则：千位分隔符（与小数点不同的 `,`/`.` 或空格）后必须恰好跟三位数字
+fn number_pattern(decimal_comma: bool) -> Regex {
+    let (decimal, thousands) = if decimal_comma {
+        (',', '.')
+    } else {
+        ('.', ',')
+    };
+    let decimal = regex::escape(&decimal.to_string());
+    let thousands = regex::escape(&thousands.to_string());
+    Regex::new(&format!(
+        r"[0-9]{{1,3}}(?:[{thousands}\u{{00A0}}\u{{202F}} ][0-9]{{3}})+(?:{decimal}[0-9]+)?|[0-9]+(?:{decimal}[0-9]+)?"
+    ))
+    .unwrap()
+}
+
+impl NumberValidator {
+    pub fn new(
+        source_lang: &str,
+        target_lang: &str,
+        markup: MarkupSyntax,
+        settings: &ValidationSettings,
+    ) -> Self {
+        let decimal_comma = |lang: &str| settings.decimal_comma_langs.iter().any(|l| l == lang);
+        Self {
+            source_decimal_comma: decimal_comma(source_lang),
+            target_decimal_comma: decimal_comma(target_lang),
+            fullwidth_digits: settings.fullwidth_digits,
+            markup,
+            source_pattern: number_pattern(decimal_comma(source_lang)),
+            target_pattern: number_pattern(decimal_comma(target_lang)),
+        }
+    }
+
+    /// 验证一个切片的译文中的数字
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (key, original, translated) in paired_entries(original, translated) {
+            self.validate_entry(key, original, translated, &mut problems);
+        }
+        problems
+    }
+
+    /// 验证单个条目，返回新增的问题数
+    ///
+    /// 数字按多重集合比较，允许译文调整顺序；
+    /// 原文与译文各自多出的数字按出现顺序配对报告为被改变，其余报告为缺失或多余
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let before = problems.len();
+        let mut missing = self.numbers(original, &self.source_pattern, self.source_decimal_comma);
+        let mut extra = Vec::new();
+        for number in self.numbers(translated, &self.target_pattern, self.target_decimal_comma) {
+            match missing.iter().position(|n| n.value == number.value) {
+                Some(i) => {
+                    missing.remove(i);
+                }
+                None => extra.push(number),
+            }
+        }
+        for (i, original) in missing.iter().enumerate() {
+            match extra.get(i) {
+                Some(translated) => problems.push(Problem::NumberMismatch {
+                    key: key.to_string(),
+                    original: original.raw.clone(),
+                    translated: translated.raw.clone(),
+                }),
+                None => problems.push(Problem::NumberNotFound {
+                    key: key.to_string(),
+                    number: original.raw.clone(),
+                }),
+            }
+        }
+        for translated in extra.iter().skip(missing.len()) {
+            problems.push(Problem::UnexpectedNumber {
+                key: key.to_string(),
+                number: translated.raw.clone(),
+            });
+        }
+        problems.len() - before
+    }
+
+    /// 提取标记之外的数字
+    fn numbers(&self, value: &str, pattern: &Regex, decimal_comma: bool) -> Vec<NumberToken> {
+        let mut plain = self.markup.plain_text(value);
+        if self.fullwidth_digits {
+            plain = plain
+                .chars()
+                .map(|c| match c {
+                    '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap(),
+                    '．' => '.',
+                    c => c,
+                })
+                .collect();
+        }
+        pattern
+            .find_iter(&plain)
+            .map(|m| {
+                let raw = m.as_str().to_string();
+                let (decimal, thousands) = if decimal_comma {
+                    (',', '.')
+                } else {
+                    ('.', ',')
+                };
+                let normalized: String = raw
+                    .chars()
+                    .filter(|c| !matches!(c, '\u{00A0}' | '\u{202F}' | ' ') && *c != thousands)
+                    .map(|c| if c == decimal { '.' } else { c })
+                    .collect();
+                let value = normalized
+                    .parse::<f64>()
+                    .map(|v| v.to_string())
+                    .unwrap_or(normalized);
+                NumberToken { raw, value }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(target_lang: &str, original: &str, translated: &str) -> Vec<String> {
+        let settings = ValidationSettings::default();
+        NumberValidator::new("english", target_lang, MarkupSyntax::STELLARIS, &settings)
+            .validate(
+                &format!("a: \"{}\"", original),
+                &format!("a: \"{}\"", translated),
+            )
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_accepts_reordered_numbers() {
+        assert!(
+            problems(
+                "simp_chinese",
+                "+10% £energy£ per 100 pops",
+                "每100人口 +10% £energy£"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_reports_missing_number() {
+        assert_eq!(
+            problems("simp_chinese", "Lasts 3 years", "持续三年"),
+            ["Number '3' of key 'a' is missing in the translation"]
+        );
+    }
+
+    #[test]
+    fn test_reports_changed_number() {
+        assert_eq!(
+            problems("simp_chinese", "Between 5 and 7", "介于5和8之间"),
+            ["Number '7' of key 'a' was changed to '8'"]
+        );
+    }
+
+    #[test]
+    fn test_reports_unexpected_number() {
+        assert_eq!(
+            problems("simp_chinese", "Between 5 and 7", "介于5和7之间，共2种"),
+            ["Number '2' of key 'a' is not in the original"]
+        );
+    }
+
+    #[test]
+    fn test_normalises_digits_and_separators() {
+        assert!(
+            problems(
+                "simp_chinese",
+                "Costs 1,000 and 2.5 $VALUE|Y0$",
+                "花费１０００和2.5 $VALUE|Y0$"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_uses_target_language_separators() {
+        assert!(problems("german", "1,000 and 2.5", "1.000 und 2,5").is_empty());
+    }
+
+    #[test]
+    fn test_comma_separated_list_is_several_numbers() {
+        assert!(problems("simp_chinese", "Tiers 1,2,3", "等级1、2、3").is_empty());
+    }
+
+    #[test]
+    fn test_space_grouped_number() {
+        assert!(problems("french", "Costs 1,000", "Coûte 1 000").is_empty());
+    }
+}
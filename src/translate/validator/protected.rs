@@ -0,0 +1,86 @@
+//! 禁止翻译文本验证
+//!
+//! 检查源文本中命中禁止翻译列表的文本，是否原样出现在译文中。
+
+use super::Problem;
+use crate::preprocess::paired_entries;
+use crate::translate::do_not_translate::DoNotTranslate;
+
+/// 禁止翻译文本验证器
+pub struct ProtectedTermValidator {
+    do_not_translate: DoNotTranslate,
+}
+
+impl ProtectedTermValidator {
+    pub fn new(do_not_translate: &DoNotTranslate) -> Self {
+        Self {
+            do_not_translate: do_not_translate.clone(),
+        }
+    }
+
+    /// 验证一个切片的译文是否保留了所有受保护的文本
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.do_not_translate.is_empty() {
+            return problems;
+        }
+        for (key, original, translated) in paired_entries(original, translated) {
+            self.validate_entry(key, original, translated, &mut problems);
+        }
+        problems
+    }
+
+    /// 验证单个条目，返回新增的问题数
+    ///
+    /// 受保护的文本在译文中出现的次数不能少于源文本
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for (start, end) in self.do_not_translate.find(original) {
+            let term = &original[start..end];
+            match counts.iter_mut().find(|(t, _)| *t == term) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((term, 1)),
+            }
+        }
+        let mut problems_added = 0;
+        for (term, count) in counts {
+            if translated.matches(term).count() < count {
+                problems.push(Problem::ProtectedTermChanged {
+                    key: key.to_string(),
+                    term: term.to_string(),
+                    found: translated.to_string(),
+                });
+                problems_added += 1;
+            }
+        }
+        problems_added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protected_term_changed() {
+        let dnt = DoNotTranslate::from_entries(&["Zro", "Team Spore"]).unwrap();
+        let validator = ProtectedTermValidator::new(&dnt);
+        let original = "a: \"Zro and more Zro\"\nb: \"By Team Spore\"\nc: \"Nothing\"";
+        let translated = "a: \"Zro 与更多的 Zro\"\nb: \"制作：孢子团队\"\nc: \"无\"";
+        let problems = validator.validate(original, translated);
+        assert_eq!(problems.len(), 1);
+        match &problems[0] {
+            Problem::ProtectedTermChanged { key, term, .. } => {
+                assert_eq!(key, "b");
+                assert_eq!(term, "Team Spore");
+            }
+            other => panic!("unexpected problem: {}", other),
+        }
+    }
+}
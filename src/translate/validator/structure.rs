@@ -0,0 +1,162 @@
+//! 条目结构验证
+//!
+//! 检查译文的值是否增减了 `\n` 转义、是否含有未转义的双引号、是否跨行、
+//! 是否含有不可见字符以及首尾是否多出了空白。
+
+use super::Problem;
+use crate::postprocess::{count_newline_escapes, is_non_printable, unescaped_quotes};
+use crate::preprocess::{LocalisationEntry, parse_entry_line};
+use std::collections::HashMap;
+
+/// 条目结构验证器
+#[derive(Debug, Default)]
+pub struct StructureValidator;
+
+impl StructureValidator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 验证一个切片的译文中每个条目的结构
+    pub fn validate(&self, original: &str, translated: &str) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        let original_map: HashMap<String, String> = parse_lines(original)
+            .0
+            .into_iter()
+            .map(|e| (e.key, e.value))
+            .collect();
+        let (translated_entries, multiline) = parse_lines(translated);
+        for key in multiline {
+            problems.push(Problem::MultilineValue { key });
+        }
+        for entry in &translated_entries {
+            if let Some(original_value) = original_map.get(&entry.key) {
+                self.validate_entry(&entry.key, original_value, &entry.value, &mut problems);
+            }
+        }
+        problems
+    }
+
+    /// 验证单个条目，返回新增的问题数
+    pub fn validate_entry(
+        &self,
+        key: &str,
+        original: &str,
+        translated: &str,
+        problems: &mut Vec<Problem>,
+    ) -> usize {
+        let before = problems.len();
+        let original_newlines = count_newline_escapes(original);
+        let translated_newlines = count_newline_escapes(translated);
+        if original_newlines != translated_newlines {
+            problems.push(Problem::NewlineCountMismatch {
+                key: key.to_string(),
+                original: original_newlines,
+                translated: translated_newlines,
+            });
+        }
+        if unescaped_quotes(translated).len() > unescaped_quotes(original).len() {
+            problems.push(Problem::UnescapedQuote {
+                key: key.to_string(),
+            });
+        }
+        if let Some(c) = translated.chars().find(|c| is_non_printable(*c)) {
+            problems.push(Problem::NonPrintableChar {
+                key: key.to_string(),
+                character: c,
+            });
+        }
+        let has_edge_space = |s: &str| s.trim() != s;
+        if has_edge_space(translated) && !has_edge_space(original) {
+            problems.push(Problem::EdgeWhitespace {
+                key: key.to_string(),
+            });
+        }
+        problems.len() - before
+    }
+}
+
+/// 解析条目，并返回值跨行的条目键名：条目后紧跟的非条目、非注释行视为上一条目的延续
+fn parse_lines(content: &str) -> (Vec<LocalisationEntry>, Vec<String>) {
+    let mut entries: Vec<LocalisationEntry> = Vec::new();
+    let mut multiline: Vec<String> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        match parse_entry_line(line, i + 1) {
+            Some(entry) => {
+                // 只有左引号的值会延续到下一行
+                let unterminated = trimmed
+                    .split_once('"')
+                    .is_some_and(|(_, rest)| unescaped_quotes(rest).is_empty());
+                if unterminated {
+                    multiline.push(entry.key.clone());
+                }
+                entries.push(entry);
+            }
+            None => {
+                if let Some(last) = entries.last()
+                    && !multiline.contains(&last.key)
+                {
+                    multiline.push(last.key.clone());
+                }
+            }
+        }
+    }
+    (entries, multiline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(original: &str, translated: &str) -> Vec<String> {
+        StructureValidator::new()
+            .validate(original, translated)
+            .iter()
+            .map(|p| p.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_reports_multiline_value() {
+        assert_eq!(
+            problems("a: \"One line\"\n", "a: \"一\n行\"\n"),
+            ["Value of key 'a' spans multiple lines"]
+        );
+    }
+
+    #[test]
+    fn test_reports_lost_newline_escape() {
+        assert_eq!(
+            problems("a: \"Line\\nnext\"\n", "a: \"行\"\n"),
+            ["Key 'a' has 1 '\\n' escapes in the original but 0 in the translation"]
+        );
+    }
+
+    #[test]
+    fn test_reports_unescaped_quotes() {
+        assert_eq!(
+            problems("b: \"Say \\\"hi\\\"\"\n", "b: \"说\"你好\"\"\n"),
+            ["Key 'b' contains unescaped double quotes"]
+        );
+    }
+
+    #[test]
+    fn test_reports_control_characters() {
+        assert_eq!(
+            problems("c: \"Plain\"\n", "c: \"普通\u{200B}\u{7}\"\n"),
+            ["Key 'c' contains non-printable character U+0007"]
+        );
+    }
+
+    #[test]
+    fn test_reports_added_whitespace() {
+        assert_eq!(
+            problems("c: \"Plain\"\n", "c: \"普通 \"\n"),
+            ["Key 'c' has leading or trailing whitespace not in the original"]
+        );
+    }
+}
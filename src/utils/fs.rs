@@ -15,12 +15,11 @@ pub fn find_yaml_files(dir: &Path) -> Result<Vec<PathBuf>> {
         .filter_map(|e| e.ok())
     {
         let path = entry.path();
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext == "yml" || ext == "yaml" {
-                    files.push(path.to_path_buf());
-                }
-            }
+        if path.is_file()
+            && let Some(ext) = path.extension()
+            && (ext == "yml" || ext == "yaml")
+        {
+            files.push(path.to_path_buf());
         }
     }
 
@@ -32,11 +31,7 @@ pub fn read_file_with_bom(path: &Path) -> Result<String> {
     let content = fs::read_to_string(path)?;
 
     // 移除UTF-8 BOM
-    let content = if content.starts_with('\u{feff}') {
-        &content[3..]
-    } else {
-        &content
-    };
+    let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
 
     Ok(content.to_string())
 }
@@ -133,3 +128,18 @@ pub fn find_data_file_or_error(relative_path: &str) -> Result<PathBuf> {
         ))
     })
 }
+
+/// 获取用于写入数据文件的路径
+///
+/// 若文件已存在于数据目录中，则返回其所在路径；否则若当前目录下存在 ./data，
+/// 则写入 ./data/相对路径，再否则写入用户数据目录。
+pub fn data_file_for_write(relative_path: &str) -> Result<PathBuf> {
+    if let Some(existing) = find_data_file(relative_path)? {
+        return Ok(existing);
+    }
+    let current_data_dir = PathBuf::from("data");
+    if current_data_dir.is_dir() {
+        return Ok(current_data_dir.join(relative_path));
+    }
+    Ok(get_user_data_dir()?.join(relative_path))
+}
@@ -3,9 +3,9 @@
 //! 提供通用辅助函数，如文件系统操作、正则表达式模式等。
 
 mod fs;
-mod token_estimator;
 mod logger;
+mod token_estimator;
 
 pub use fs::*;
+pub use logger::*;
 pub use token_estimator::*;
-pub use logger::*;
\ No newline at end of file